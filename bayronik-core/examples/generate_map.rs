@@ -1,15 +1,17 @@
-use bayronik_core::{run_simulation, output};
+use bayronik_core::{output, run_simulation, AssignmentScheme};
 
 fn main() -> anyhow::Result<()> {
     println!("Generating N-body simulation map...");
-    
+
     let map = run_simulation(
-        32_768,  // particles
-        64,      // grid resolution
+        64,      // grid resolution (64^3 particles)
         100.0,   // box size (Mpc/h)
         0.01,    // time step
         10,      // steps
         256,     // output resolution
+        AssignmentScheme::Cic,
+        42,      // IC seed
+        true,    // interlace grids to suppress assignment aliasing
     );
     
     let output_path = "nbody_map_256.npy";
@@ -1,54 +1,259 @@
 pub mod output;
 pub mod sim;
 
+pub use sim::boundary::BoundaryCondition;
+pub use sim::cosmology::Cosmology;
 pub use sim::fft_solver::FftSolver;
 pub use sim::forces;
 pub use sim::gravity;
+pub use sim::gravity::AssignmentScheme;
 pub use sim::grid::Grid;
 pub use sim::particle::ParticleSet;
+pub use sim::power_spectrum;
+pub use sim::power_spectrum::PowerSpectrum;
 
-/// Run a complete N-body simulation and return 2D projection.
+/// A featureless power-law power spectrum `P(k) = A * k^-n`, a reasonable
+/// stand-in for a CDM transfer function when the caller has no tabulated
+/// spectrum of their own. A thin function wrapper around
+/// `PowerSpectrum::PowerLaw` so existing callers of `initialize_zeldovich`
+/// that expect a bare `Fn(f32) -> f32` don't need to change.
+pub fn default_power_spectrum(k: f32) -> f32 {
+    const AMPLITUDE: f32 = 5.0e4;
+    const SPECTRAL_INDEX: f32 = 1.5;
+    PowerSpectrum::PowerLaw {
+        amplitude: AMPLITUDE,
+        index: SPECTRAL_INDEX,
+    }
+    .eval(k)
+}
+
+/// Run a complete N-body simulation and return 2D projection. Particle count
+/// is tied to `grid_resolution^3` (one particle per cell), and initial
+/// conditions are drawn from `default_power_spectrum` via the Zel'dovich
+/// approximation, seeded by `seed` for reproducibility. When `interlaced` is
+/// set, each force solve assigns mass to a second half-cell-shifted grid and
+/// combines the two spectra to suppress assignment aliasing, at the cost of
+/// one extra mass assignment + FFT per solve (see
+/// `gravity::assign_mass_interlaced` / `FftSolver::solve_forces_interlaced`).
 pub fn run_simulation(
-    num_particles: usize,
     grid_resolution: usize,
     box_size: f32,
     time_step: f32,
     num_steps: usize,
     projection_res: usize,
+    scheme: AssignmentScheme,
+    seed: u64,
+    interlaced: bool,
+) -> Vec<f32> {
+    run_simulation_with_progress(
+        grid_resolution,
+        box_size,
+        time_step,
+        num_steps,
+        projection_res,
+        scheme,
+        seed,
+        interlaced,
+        |_| {},
+    )
+}
+
+/// Like `run_simulation`, but calls `on_step(step)` after each completed
+/// leapfrog step (`step` in `0..num_steps`), so a caller running this on a
+/// background thread can report live integration progress back to a UI
+/// thread instead of the caller only finding out once the whole run (and
+/// its final projection) is done.
+pub fn run_simulation_with_progress(
+    grid_resolution: usize,
+    box_size: f32,
+    time_step: f32,
+    num_steps: usize,
+    projection_res: usize,
+    scheme: AssignmentScheme,
+    seed: u64,
+    interlaced: bool,
+    mut on_step: impl FnMut(usize),
 ) -> Vec<f32> {
     let mut particles = ParticleSet::new();
-    particles.initialize_grid_with_perturbations(num_particles, box_size);
-    
+    particles.initialize_zeldovich(
+        grid_resolution,
+        box_size,
+        default_power_spectrum,
+        seed,
+        1.0,
+        1.0,
+    );
+
     let mut grid = Grid::new(grid_resolution, box_size);
+    let mut grid_shifted = Grid::new(grid_resolution, box_size);
     let mut fft_solver = FftSolver::new(grid_resolution);
-    
-    // Add gravitational amplification factor to grow perturbations faster
-    let growth_factor = 2.5;
-    
-    for _ in 0..num_steps {
+
+    let solve_forces = |particles: &ParticleSet,
+                         grid: &mut Grid,
+                         grid_shifted: &mut Grid,
+                         fft_solver: &mut FftSolver| {
         grid.clear_density();
-        gravity::assign_mass_cic(&particles, &mut grid);
-        fft_solver.solve_potential(&mut grid);
-        
-        let (mut fx, mut fy, mut fz) = forces::calculate_forces_from_potential(&grid);
-        
-        // Amplify gravitational forces to accelerate structure formation
-        for f in &mut fx { *f *= growth_factor; }
-        for f in &mut fy { *f *= growth_factor; }
-        for f in &mut fz { *f *= growth_factor; }
-        
-        forces::interpolate_forces_to_particles(&mut particles, &grid, &fx, &fy, &fz);
-        
-        particles.integrate(time_step);
-        
-        let (mut fx, mut fy, mut fz) = forces::calculate_forces_from_potential(&grid);
-        for f in &mut fx { *f *= growth_factor; }
-        for f in &mut fy { *f *= growth_factor; }
-        for f in &mut fz { *f *= growth_factor; }
-        
-        forces::interpolate_forces_to_particles(&mut particles, &grid, &fx, &fy, &fz);
-        particles.kick(time_step);
+        if interlaced {
+            grid_shifted.clear_density();
+            gravity::assign_mass_interlaced(particles, grid, grid_shifted, scheme);
+            fft_solver.solve_forces_interlaced(grid, grid_shifted, scheme)
+        } else {
+            gravity::assign_mass(particles, grid, scheme);
+            // Forces come straight out of the FFT solver via
+            // ik-differentiation, which has no stencil error at high k
+            // (unlike the finite-difference path in
+            // forces::calculate_forces_from_potential).
+            fft_solver.solve_forces(grid, scheme)
+        }
+    };
+
+    // Kick-drift-kick leapfrog: each step needs the force chain rerun twice,
+    // once at the pre-drift positions and once at the post-drift positions,
+    // so the two half-kicks bracket a genuine recomputation rather than
+    // reusing a stale force array.
+    for step in 0..num_steps {
+        let (fx, fy, fz) = solve_forces(&particles, &mut grid, &mut grid_shifted, &mut fft_solver);
+        forces::interpolate_forces_to_particles(&mut particles, &grid, &fx, &fy, &fz, scheme);
+
+        particles.half_kick(time_step);
+        particles.drift(time_step);
+
+        let (fx, fy, fz) = solve_forces(&particles, &mut grid, &mut grid_shifted, &mut fft_solver);
+        forces::interpolate_forces_to_particles(&mut particles, &grid, &fx, &fy, &fz, scheme);
+
+        particles.half_kick(time_step);
+        on_step(step);
     }
-    
+
+    particles.project_to_2d(projection_res)
+}
+
+/// Like `run_simulation`, but steps the KDK leapfrog in the scale factor
+/// `a` under `cosmology` rather than a fixed `dt`: `drift_cosmological`
+/// and `kick_cosmological` use `Cosmology::drift_factor`/`kick_factor` in
+/// place of fixed-`dt` prefactors, the force solve uses
+/// `gravity::compute_pm_forces` with `Cosmology::poisson_normalization(a)`
+/// instead of a constant Poisson normalization, and the Zel'dovich initial
+/// velocities use `Cosmology::velocity_prefactor(a_start)` (the linear
+/// growth rate `f = dlnD/dlna` times `H(a_start)`) instead of an ad-hoc
+/// `velocity_scale`. `a_start` and `a_end` bound the scale factor swept
+/// over `num_steps` equal-`da` steps.
+pub fn run_simulation_cosmological(
+    grid_resolution: usize,
+    box_size: f32,
+    a_start: f32,
+    a_end: f32,
+    num_steps: usize,
+    projection_res: usize,
+    scheme: AssignmentScheme,
+    seed: u64,
+    cosmology: Cosmology,
+) -> Vec<f32> {
+    let mut particles = ParticleSet::new();
+    particles.initialize_zeldovich(
+        grid_resolution,
+        box_size,
+        default_power_spectrum,
+        seed,
+        a_start,
+        cosmology.velocity_prefactor(a_start),
+    );
+
+    let mut grid = Grid::new(grid_resolution, box_size);
+    let mut fft_solver = FftSolver::new(grid_resolution);
+    let da = (a_end - a_start) / num_steps as f32;
+
+    // Kick-drift-kick leapfrog in scale-factor steps: each step's half-kicks
+    // bracket a drift covering the full da, with the force chain rerun at
+    // the drifted positions just like run_simulation's fixed-dt version.
+    let mut a = a_start;
+    for _ in 0..num_steps {
+        gravity::compute_pm_forces(
+            &mut particles,
+            &mut grid,
+            &mut fft_solver,
+            scheme,
+            cosmology.poisson_normalization(a),
+        );
+        particles.kick_cosmological(cosmology.kick_factor(a, da * 0.5));
+
+        particles.drift_cosmological(cosmology.drift_factor(a, da));
+        a += da;
+
+        gravity::compute_pm_forces(
+            &mut particles,
+            &mut grid,
+            &mut fft_solver,
+            scheme,
+            cosmology.poisson_normalization(a),
+        );
+        particles.kick_cosmological(cosmology.kick_factor(a - da * 0.5, da * 0.5));
+    }
+
+    particles.project_to_2d(projection_res)
+}
+
+/// Like `run_simulation`, but drifts and solves forces under `boundary`
+/// instead of assuming a fully periodic box. Passing
+/// `BoundaryCondition::ShearingSheet { shear_rate_q, omega }` simulates a
+/// small co-rotating, differentially-rotating patch (e.g. a local disk
+/// annulus) rather than a cosmological box: each step's drift tracks the
+/// simulation time `t` so `particles::drift_with_boundary` can apply the
+/// accumulated azimuthal shear to any particle crossing the radial (x)
+/// boundary, and each force solve re-derives the same shear offset for the
+/// mass assignment / force interpolation grids via
+/// `gravity::compute_pm_forces_with_boundary`, keeping the density field
+/// continuous across the sheared edge.
+pub fn run_simulation_shearing_sheet(
+    grid_resolution: usize,
+    box_size: f32,
+    time_step: f32,
+    num_steps: usize,
+    projection_res: usize,
+    scheme: AssignmentScheme,
+    seed: u64,
+    poisson_normalization: f32,
+    boundary: BoundaryCondition,
+) -> Vec<f32> {
+    let mut particles = ParticleSet::new();
+    particles.initialize_zeldovich(
+        grid_resolution,
+        box_size,
+        default_power_spectrum,
+        seed,
+        1.0,
+        1.0,
+    );
+
+    let mut grid = Grid::new(grid_resolution, box_size);
+    let mut fft_solver = FftSolver::new(grid_resolution);
+
+    let mut time = 0.0;
+    for _ in 0..num_steps {
+        gravity::compute_pm_forces_with_boundary(
+            &mut particles,
+            &mut grid,
+            &mut fft_solver,
+            scheme,
+            poisson_normalization,
+            boundary,
+            time,
+        );
+        particles.half_kick(time_step);
+        particles.drift_with_boundary(time_step, time, &boundary);
+        time += time_step;
+
+        gravity::compute_pm_forces_with_boundary(
+            &mut particles,
+            &mut grid,
+            &mut fft_solver,
+            scheme,
+            poisson_normalization,
+            boundary,
+            time,
+        );
+        particles.half_kick(time_step);
+    }
+
     particles.project_to_2d(projection_res)
 }
@@ -3,23 +3,32 @@ mod sim;
 use sim::fft_solver::FftSolver;
 use sim::forces;
 use sim::gravity;
+use sim::gravity::AssignmentScheme;
 use sim::grid::Grid;
 use sim::particle::ParticleSet;
 
 fn main() {
     println!("bayronik-core: N-body PM simulation");
 
-    const NUM_PARTICLES: usize = 32_768;
     const GRID_RESOLUTION: usize = 64;
     const BOX_SIZE: f32 = 100.0;
     const TIME_STEP: f32 = 0.01;
     const NUM_STEPS: usize = 10;
+    const SCHEME: AssignmentScheme = AssignmentScheme::Cic;
+    const SEED: u64 = 42;
 
     let mut particles = ParticleSet::new();
-    particles.initialize_grid_with_perturbations(NUM_PARTICLES, BOX_SIZE);
+    particles.initialize_zeldovich(
+        GRID_RESOLUTION,
+        BOX_SIZE,
+        bayronik_core::default_power_spectrum,
+        SEED,
+        1.0,
+        1.0,
+    );
     println!(
-        "Initialized {} particles in {}^3 Mpc/h box",
-        NUM_PARTICLES, BOX_SIZE
+        "Initialized {}^3 particles in {}^3 Mpc/h box",
+        GRID_RESOLUTION, BOX_SIZE
     );
 
     let mut grid = Grid::new(GRID_RESOLUTION, BOX_SIZE);
@@ -30,17 +39,20 @@ fn main() {
         println!("Step {}/{}", step + 1, NUM_STEPS);
 
         grid.clear_density();
-        gravity::assign_mass_cic(&particles, &mut grid);
-        fft_solver.solve_potential(&mut grid);
+        gravity::assign_mass(&particles, &mut grid, SCHEME);
+
+        let (fx, fy, fz) = fft_solver.solve_forces(&grid, SCHEME);
+        forces::interpolate_forces_to_particles(&mut particles, &grid, &fx, &fy, &fz, SCHEME);
 
-        let (fx, fy, fz) = forces::calculate_forces_from_potential(&grid);
-        forces::interpolate_forces_to_particles(&mut particles, &grid, &fx, &fy, &fz);
+        particles.half_kick(TIME_STEP);
+        particles.drift(TIME_STEP);
 
-        particles.integrate(TIME_STEP);
+        grid.clear_density();
+        gravity::assign_mass(&particles, &mut grid, SCHEME);
+        let (fx_new, fy_new, fz_new) = fft_solver.solve_forces(&grid, SCHEME);
+        forces::interpolate_forces_to_particles(&mut particles, &grid, &fx_new, &fy_new, &fz_new, SCHEME);
 
-        let (fx_new, fy_new, fz_new) = forces::calculate_forces_from_potential(&grid);
-        forces::interpolate_forces_to_particles(&mut particles, &grid, &fx_new, &fy_new, &fz_new);
-        particles.kick(TIME_STEP);
+        particles.half_kick(TIME_STEP);
     }
 
     println!("\nProjecting to 2D map (256x256)...");
@@ -0,0 +1,84 @@
+//! Cosmological background quantities (Hubble rate, linear growth) used to
+//! step the leapfrog integrator in the scale factor `a` rather than a fixed
+//! time step, and to set the comoving Poisson normalization.
+
+/// A flat FLRW background: `Omega_m + Omega_Lambda = 1` is assumed by the
+/// Friedmann equation used here, though the struct doesn't enforce it.
+#[derive(Debug, Clone, Copy)]
+pub struct Cosmology {
+    pub omega_m: f32,
+    pub omega_lambda: f32,
+    pub h0: f32,
+}
+
+impl Cosmology {
+    pub fn new(omega_m: f32, omega_lambda: f32, h0: f32) -> Self {
+        Self {
+            omega_m,
+            omega_lambda,
+            h0,
+        }
+    }
+
+    /// Friedmann equation: `H(a) = H0 * sqrt(Omega_m * a^-3 + Omega_Lambda)`.
+    pub fn hubble(&self, a: f32) -> f32 {
+        self.h0 * (self.omega_m * a.powi(-3) + self.omega_lambda).sqrt()
+    }
+
+    /// The matter density parameter at scale factor `a`,
+    /// `Omega_m(a) = Omega_m * a^-3 * H0^2 / H(a)^2`, used by `growth_rate`.
+    pub fn omega_m_at(&self, a: f32) -> f32 {
+        let h = self.hubble(a);
+        self.omega_m * a.powi(-3) * self.h0 * self.h0 / (h * h)
+    }
+
+    /// Linear growth rate `f = dlnD/dlna`, via the standard fitting formula
+    /// `f ~= Omega_m(a)^0.55` (accurate to ~1% across LambdaCDM-like
+    /// histories). Used in place of the Zel'dovich approximation's ad-hoc
+    /// `velocity_prefactor` so the initial peculiar velocities are
+    /// consistent with this cosmology at the starting scale factor.
+    pub fn growth_rate(&self, a: f32) -> f32 {
+        self.omega_m_at(a).powf(0.55)
+    }
+
+    /// `f(a) * H(a)`, the prefactor the Zel'dovich velocity relation
+    /// `v = f*H * Psi` needs, evaluated at the starting scale factor `a`.
+    pub fn velocity_prefactor(&self, a: f32) -> f32 {
+        self.growth_rate(a) * self.hubble(a)
+    }
+
+    /// Comoving Poisson normalization `(3/2) * Omega_m * H0^2 / a`, which
+    /// `gravity::compute_pm_forces` folds into the Green's function in
+    /// place of a fixed `4*pi*G / mean_density`.
+    pub fn poisson_normalization(&self, a: f32) -> f32 {
+        1.5 * self.omega_m * self.h0 * self.h0 / a
+    }
+
+    /// Drift factor `integral_a^{a+da} da' / (a'^2 * H(a'))`, the comoving
+    /// leapfrog's replacement for a fixed `dt` in `ParticleSet::drift`.
+    pub fn drift_factor(&self, a: f32, da: f32) -> f32 {
+        simpson_integrate(|a| 1.0 / (a * a * self.hubble(a)), a, a + da)
+    }
+
+    /// Kick factor `integral_a^{a+da} da' / (a' * H(a'))`, the comoving
+    /// leapfrog's replacement for a fixed `dt` in `ParticleSet::half_kick`.
+    pub fn kick_factor(&self, a: f32, da: f32) -> f32 {
+        simpson_integrate(|a| 1.0 / (a * self.hubble(a)), a, a + da)
+    }
+}
+
+/// Composite Simpson's rule over a fixed number of sub-intervals. `da` per
+/// leapfrog step is small relative to the Hubble time, so a fixed,
+/// moderate subdivision count keeps this accurate without pulling in a
+/// quadrature crate.
+fn simpson_integrate(f: impl Fn(f32) -> f32, a0: f32, a1: f32) -> f32 {
+    const STEPS: usize = 16;
+    let h = (a1 - a0) / STEPS as f32;
+
+    let mut sum = f(a0) + f(a1);
+    for i in 1..STEPS {
+        let a = a0 + i as f32 * h;
+        sum += if i % 2 == 0 { 2.0 } else { 4.0 } * f(a);
+    }
+    sum * h / 3.0
+}
@@ -1,3 +1,5 @@
+use super::boundary::{wrapped_index, BoundaryCondition};
+use super::gravity::{pcs_weights, tsc_weights, AssignmentScheme};
 use super::{grid::Grid, particle::ParticleSet};
 
 /// Compute gravitational forces on grid from potential using finite differences.
@@ -32,34 +34,158 @@ pub fn calculate_forces_from_potential(grid: &Grid) -> (Vec<f32>, Vec<f32>, Vec<
     (fx, fy, fz)
 }
 
-/// Interpolate forces from grid to particles using CIC.
+/// Interpolate forces from grid to particles using the given scheme, under
+/// plain periodic wrapping (see `interpolate_forces_to_particles_with_boundary`
+/// for the shearing-sheet-aware version).
 pub fn interpolate_forces_to_particles(
     particles: &mut ParticleSet,
     grid: &Grid,
     fx: &[f32],
     fy: &[f32],
     fz: &[f32],
+    scheme: AssignmentScheme,
+) {
+    interpolate_forces_to_particles_with_boundary(
+        particles,
+        grid,
+        fx,
+        fy,
+        fz,
+        scheme,
+        BoundaryCondition::Periodic,
+        0.0,
+    )
+}
+
+/// Interpolate forces from grid to particles using the given scheme and
+/// `boundary`. For `BoundaryCondition::ShearingSheet`, any stencil cell that
+/// wraps across the x-boundary is also offset in y, matching the shift
+/// `gravity::assign_mass_with_boundary` applied when building the grid this
+/// force field came from.
+pub fn interpolate_forces_to_particles_with_boundary(
+    particles: &mut ParticleSet,
+    grid: &Grid,
+    fx: &[f32],
+    fy: &[f32],
+    fz: &[f32],
+    scheme: AssignmentScheme,
+    boundary: BoundaryCondition,
+    time: f32,
+) {
+    let shear_shift_cells = boundary.shear_shift_cells(grid.box_size, grid.resolution, time);
+    match scheme {
+        AssignmentScheme::Ngp => interpolate_forces_ngp(particles, grid, fx, fy, fz, shear_shift_cells),
+        AssignmentScheme::Cic => interpolate_forces_cic(particles, grid, fx, fy, fz, shear_shift_cells),
+        AssignmentScheme::Tsc => interpolate_forces_tsc(particles, grid, fx, fy, fz, shear_shift_cells),
+        AssignmentScheme::Pcs => interpolate_forces_pcs(particles, grid, fx, fy, fz, shear_shift_cells),
+    }
+}
+
+/// Interpolate forces from grid to particles using NGP, matching the
+/// single-cell footprint of `gravity::assign_mass_ngp`.
+fn interpolate_forces_ngp(
+    particles: &mut ParticleSet,
+    grid: &Grid,
+    fx: &[f32],
+    fy: &[f32],
+    fz: &[f32],
+    shear_shift_cells: isize,
 ) {
     let n = grid.resolution;
     let n_f32 = n as f32;
     let cell_size = grid.box_size / n_f32;
     let inv_cell_size = 1.0 / cell_size;
-    
+    let n_isize = n as isize;
+
+    for p in &mut particles.particles {
+        let i = (p.position[0] * inv_cell_size).round() as isize;
+        let j = (p.position[1] * inv_cell_size).round() as isize;
+        let k = (p.position[2] * inv_cell_size).round() as isize;
+        let idx = wrapped_index(i, j, k, n_isize, shear_shift_cells);
+
+        p.force = [fx[idx], fy[idx], fz[idx]];
+    }
+}
+
+/// Interpolate forces from grid to particles using PCS, matching the
+/// 64-cell footprint of `gravity::assign_mass_pcs`.
+fn interpolate_forces_pcs(
+    particles: &mut ParticleSet,
+    grid: &Grid,
+    fx: &[f32],
+    fy: &[f32],
+    fz: &[f32],
+    shear_shift_cells: isize,
+) {
+    let n = grid.resolution;
+    let n_f32 = n as f32;
+    let cell_size = grid.box_size / n_f32;
+    let inv_cell_size = 1.0 / cell_size;
+    let n_isize = n as isize;
+
+    for p in &mut particles.particles {
+        let pos_grid = [
+            p.position[0] * inv_cell_size,
+            p.position[1] * inv_cell_size,
+            p.position[2] * inv_cell_size,
+        ];
+
+        let (i, wx) = pcs_weights(pos_grid[0]);
+        let (j, wy) = pcs_weights(pos_grid[1]);
+        let (k, wz) = pcs_weights(pos_grid[2]);
+
+        let mut force = [0.0f32; 3];
+        for (di, &wx_i) in wx.iter().enumerate() {
+            for (dj, &wy_j) in wy.iter().enumerate() {
+                for (dk, &wz_k) in wz.iter().enumerate() {
+                    let idx = wrapped_index(
+                        i + di as isize - 1,
+                        j + dj as isize - 1,
+                        k + dk as isize - 1,
+                        n_isize,
+                        shear_shift_cells,
+                    );
+                    let weight = wx_i * wy_j * wz_k;
+                    force[0] += weight * fx[idx];
+                    force[1] += weight * fy[idx];
+                    force[2] += weight * fz[idx];
+                }
+            }
+        }
+        p.force = force;
+    }
+}
+
+/// Interpolate forces from grid to particles using CIC.
+fn interpolate_forces_cic(
+    particles: &mut ParticleSet,
+    grid: &Grid,
+    fx: &[f32],
+    fy: &[f32],
+    fz: &[f32],
+    shear_shift_cells: isize,
+) {
+    let n = grid.resolution;
+    let n_f32 = n as f32;
+    let cell_size = grid.box_size / n_f32;
+    let inv_cell_size = 1.0 / cell_size;
+    let n_isize = n as isize;
+
     for p in &mut particles.particles {
         let pos_grid = [
             p.position[0] * inv_cell_size,
             p.position[1] * inv_cell_size,
             p.position[2] * inv_cell_size,
         ];
-        
+
         let i = pos_grid[0].floor() as isize;
         let j = pos_grid[1].floor() as isize;
         let k = pos_grid[2].floor() as isize;
-        
+
         let dx = pos_grid[0] - i as f32;
         let dy = pos_grid[1] - j as f32;
         let dz = pos_grid[2] - k as f32;
-        
+
         let w = [
             (1.0 - dx) * (1.0 - dy) * (1.0 - dz),
             dx * (1.0 - dy) * (1.0 - dz),
@@ -70,22 +196,70 @@ pub fn interpolate_forces_to_particles(
             (1.0 - dx) * dy * dz,
             dx * dy * dz,
         ];
-        
-        let n_isize = n as isize;
+
         let indices = [
-            (((i % n_isize + n_isize) % n_isize * n_isize + (j % n_isize + n_isize) % n_isize) * n_isize + (k % n_isize + n_isize) % n_isize) as usize,
-            ((((i + 1) % n_isize + n_isize) % n_isize * n_isize + (j % n_isize + n_isize) % n_isize) * n_isize + (k % n_isize + n_isize) % n_isize) as usize,
-            (((i % n_isize + n_isize) % n_isize * n_isize + ((j + 1) % n_isize + n_isize) % n_isize) * n_isize + (k % n_isize + n_isize) % n_isize) as usize,
-            (((i % n_isize + n_isize) % n_isize * n_isize + (j % n_isize + n_isize) % n_isize) * n_isize + ((k + 1) % n_isize + n_isize) % n_isize) as usize,
-            ((((i + 1) % n_isize + n_isize) % n_isize * n_isize + ((j + 1) % n_isize + n_isize) % n_isize) * n_isize + (k % n_isize + n_isize) % n_isize) as usize,
-            ((((i + 1) % n_isize + n_isize) % n_isize * n_isize + (j % n_isize + n_isize) % n_isize) * n_isize + ((k + 1) % n_isize + n_isize) % n_isize) as usize,
-            (((i % n_isize + n_isize) % n_isize * n_isize + ((j + 1) % n_isize + n_isize) % n_isize) * n_isize + ((k + 1) % n_isize + n_isize) % n_isize) as usize,
-            ((((i + 1) % n_isize + n_isize) % n_isize * n_isize + ((j + 1) % n_isize + n_isize) % n_isize) * n_isize + ((k + 1) % n_isize + n_isize) % n_isize) as usize,
+            wrapped_index(i, j, k, n_isize, shear_shift_cells),
+            wrapped_index(i + 1, j, k, n_isize, shear_shift_cells),
+            wrapped_index(i, j + 1, k, n_isize, shear_shift_cells),
+            wrapped_index(i, j, k + 1, n_isize, shear_shift_cells),
+            wrapped_index(i + 1, j + 1, k, n_isize, shear_shift_cells),
+            wrapped_index(i + 1, j, k + 1, n_isize, shear_shift_cells),
+            wrapped_index(i, j + 1, k + 1, n_isize, shear_shift_cells),
+            wrapped_index(i + 1, j + 1, k + 1, n_isize, shear_shift_cells),
         ];
-        
+
         p.force[0] = (0..8).map(|idx| w[idx] * fx[indices[idx]]).sum();
         p.force[1] = (0..8).map(|idx| w[idx] * fy[indices[idx]]).sum();
         p.force[2] = (0..8).map(|idx| w[idx] * fz[indices[idx]]).sum();
     }
 }
 
+/// Interpolate forces from grid to particles using TSC, matching the 27-cell
+/// footprint of `gravity::assign_mass_tsc`.
+fn interpolate_forces_tsc(
+    particles: &mut ParticleSet,
+    grid: &Grid,
+    fx: &[f32],
+    fy: &[f32],
+    fz: &[f32],
+    shear_shift_cells: isize,
+) {
+    let n = grid.resolution;
+    let n_f32 = n as f32;
+    let cell_size = grid.box_size / n_f32;
+    let inv_cell_size = 1.0 / cell_size;
+    let n_isize = n as isize;
+
+    for p in &mut particles.particles {
+        let pos_grid = [
+            p.position[0] * inv_cell_size,
+            p.position[1] * inv_cell_size,
+            p.position[2] * inv_cell_size,
+        ];
+
+        let (i, wx) = tsc_weights(pos_grid[0]);
+        let (j, wy) = tsc_weights(pos_grid[1]);
+        let (k, wz) = tsc_weights(pos_grid[2]);
+
+        let mut force = [0.0f32; 3];
+        for (di, &wx_i) in wx.iter().enumerate() {
+            for (dj, &wy_j) in wy.iter().enumerate() {
+                for (dk, &wz_k) in wz.iter().enumerate() {
+                    let idx = wrapped_index(
+                        i + di as isize - 1,
+                        j + dj as isize - 1,
+                        k + dk as isize - 1,
+                        n_isize,
+                        shear_shift_cells,
+                    );
+                    let weight = wx_i * wy_j * wz_k;
+                    force[0] += weight * fx[idx];
+                    force[1] += weight * fy[idx];
+                    force[2] += weight * fz[idx];
+                }
+            }
+        }
+        p.force = force;
+    }
+}
+
@@ -1,11 +1,206 @@
 //! This module handles gravity-related calculations like mass assignment
 //! and force computation.
 
+use super::boundary::{wrapped_index, BoundaryCondition};
+use super::fft_solver::FftSolver;
+use super::forces;
 use super::{grid::Grid, particle::ParticleSet};
 
+/// Selects the mass-assignment / interpolation scheme used to move mass
+/// between particles and the grid. Higher orders trade compute for a
+/// smoother (less aliased) density field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssignmentScheme {
+    /// Nearest-Grid-Point: all mass dumped in the single nearest cell.
+    Ngp,
+    /// Cloud-in-Cell: linear weighting over the 8 surrounding cells.
+    Cic,
+    /// Triangular-Shaped-Cloud: quadratic weighting over the 27 surrounding cells.
+    Tsc,
+    /// Piecewise-Cubic-Spline: cubic weighting over the 64 surrounding cells.
+    Pcs,
+}
+
+impl AssignmentScheme {
+    /// Order `p` of the scheme's Fourier-space assignment window, used to
+    /// deconvolve the smoothing it imposes (see `FftSolver::solve_potential`).
+    pub fn window_order(self) -> i32 {
+        match self {
+            AssignmentScheme::Ngp => 1,
+            AssignmentScheme::Cic => 2,
+            AssignmentScheme::Tsc => 3,
+            AssignmentScheme::Pcs => 4,
+        }
+    }
+}
+
+/// Assigns particle mass to the grid using the given scheme, under plain
+/// periodic wrapping (see `assign_mass_with_boundary` for the
+/// shearing-sheet-aware version).
+pub fn assign_mass(particles: &ParticleSet, grid: &mut Grid, scheme: AssignmentScheme) {
+    assign_mass_with_boundary(particles, grid, scheme, BoundaryCondition::Periodic, 0.0)
+}
+
+/// Assigns particle mass to the grid using the given scheme and
+/// `boundary`. For `BoundaryCondition::ShearingSheet`, any stencil cell that
+/// wraps across the x-boundary is also offset in y by
+/// `boundary.shear_shift_cells(grid.box_size, grid.resolution, time)`, so the
+/// density field on either side of the radial edge is continuous with the
+/// sheared copy of the box rather than the plain periodic one.
+pub fn assign_mass_with_boundary(
+    particles: &ParticleSet,
+    grid: &mut Grid,
+    scheme: AssignmentScheme,
+    boundary: BoundaryCondition,
+    time: f32,
+) {
+    let shear_shift_cells = boundary.shear_shift_cells(grid.box_size, grid.resolution, time);
+    match scheme {
+        AssignmentScheme::Ngp => assign_mass_ngp(particles, grid, 0.0, shear_shift_cells),
+        AssignmentScheme::Cic => assign_mass_cic(particles, grid, 0.0, shear_shift_cells),
+        AssignmentScheme::Tsc => assign_mass_tsc(particles, grid, 0.0, shear_shift_cells),
+        AssignmentScheme::Pcs => assign_mass_pcs(particles, grid, 0.0, shear_shift_cells),
+    }
+}
+
+/// Assigns mass to two grids for an interlaced PM force: `grid` at the
+/// particles' actual positions, `grid_shifted` with the assignment window
+/// shifted by half a cell along each axis. Pass both Fourier transforms to
+/// `FftSolver::solve_forces_interlaced`, which combines them as
+/// `delta_combined(k) = 0.5 * (delta_1(k) + e^{-i*k*(H/2)} * delta_2(k))` to
+/// cancel the leading aliased image from the finite assignment window.
+pub fn assign_mass_interlaced(
+    particles: &ParticleSet,
+    grid: &mut Grid,
+    grid_shifted: &mut Grid,
+    scheme: AssignmentScheme,
+) {
+    match scheme {
+        AssignmentScheme::Ngp => {
+            assign_mass_ngp(particles, grid, 0.0, 0);
+            assign_mass_ngp(particles, grid_shifted, 0.5, 0);
+        }
+        AssignmentScheme::Cic => {
+            assign_mass_cic(particles, grid, 0.0, 0);
+            assign_mass_cic(particles, grid_shifted, 0.5, 0);
+        }
+        AssignmentScheme::Tsc => {
+            assign_mass_tsc(particles, grid, 0.0, 0);
+            assign_mass_tsc(particles, grid_shifted, 0.5, 0);
+        }
+        AssignmentScheme::Pcs => {
+            assign_mass_pcs(particles, grid, 0.0, 0);
+            assign_mass_pcs(particles, grid_shifted, 0.5, 0);
+        }
+    }
+}
+
+/// Assigns particle mass to the grid using Nearest-Grid-Point (NGP): all of
+/// a particle's mass goes to the single nearest cell. The simplest (and
+/// noisiest) scheme in the family; mainly useful as a baseline to compare
+/// the higher-order schemes against. `cell_offset` shifts the assignment
+/// window by that many cells along each axis (0.0 for a normal assignment,
+/// 0.5 for the interlaced partner). `shear_shift_cells` offsets the y-index
+/// by that many cells for every box-width the x-index wraps by (0 under
+/// plain periodic wrapping; see `boundary::wrapped_index`).
+pub fn assign_mass_ngp(particles: &ParticleSet, grid: &mut Grid, cell_offset: f32, shear_shift_cells: isize) {
+    let n = grid.resolution;
+    let n_f32 = n as f32;
+    let cell_size = grid.box_size / n_f32;
+    let inv_cell_size = 1.0 / cell_size;
+
+    let total_mass: f32 = particles.particles.iter().map(|p| p.mass).sum();
+    let total_cells = (n * n * n) as f32;
+    let mean_density = total_mass / total_cells;
+
+    let n_isize = n as isize;
+
+    for p in &particles.particles {
+        let pos_grid = [
+            p.position[0] * inv_cell_size - cell_offset,
+            p.position[1] * inv_cell_size - cell_offset,
+            p.position[2] * inv_cell_size - cell_offset,
+        ];
+
+        let i = pos_grid[0].round() as isize;
+        let j = pos_grid[1].round() as isize;
+        let k = pos_grid[2].round() as isize;
+
+        let idx = wrapped_index(i, j, k, n_isize, shear_shift_cells);
+        grid.density_contrast[idx] += p.mass;
+    }
+
+    if mean_density > 1e-6 {
+        for val in &mut grid.density_contrast {
+            *val = (*val / mean_density) - 1.0;
+        }
+    }
+}
+
+/// Assigns particle mass to the grid using Piecewise-Cubic-Spline (PCS):
+/// each particle is spread over the 4 nearest cells per axis (64 cells
+/// total) using the cubic B-spline weights from `pcs_weights`, the next
+/// rung up from TSC's quadratic stencil. `cell_offset` shifts the
+/// assignment window by that many cells along each axis (0.0 for a normal
+/// assignment, 0.5 for the interlaced partner). `shear_shift_cells` offsets
+/// the y-index by that many cells for every box-width the x-index wraps by
+/// (0 under plain periodic wrapping; see `boundary::wrapped_index`).
+pub fn assign_mass_pcs(particles: &ParticleSet, grid: &mut Grid, cell_offset: f32, shear_shift_cells: isize) {
+    let n = grid.resolution;
+    let n_f32 = n as f32;
+    let cell_size = grid.box_size / n_f32;
+    let inv_cell_size = 1.0 / cell_size;
+
+    let total_mass: f32 = particles.particles.iter().map(|p| p.mass).sum();
+    let total_cells = (n * n * n) as f32;
+    let mean_density = total_mass / total_cells;
+
+    let n_isize = n as isize;
+
+    for p in &particles.particles {
+        let pos_grid = [
+            p.position[0] * inv_cell_size - cell_offset,
+            p.position[1] * inv_cell_size - cell_offset,
+            p.position[2] * inv_cell_size - cell_offset,
+        ];
+
+        // Floor cell per axis, and the four per-axis cubic B-spline
+        // weights around it (nodes at base-1, base, base+1, base+2).
+        let (i, wx) = pcs_weights(pos_grid[0]);
+        let (j, wy) = pcs_weights(pos_grid[1]);
+        let (k, wz) = pcs_weights(pos_grid[2]);
+
+        for (di, &wx_i) in wx.iter().enumerate() {
+            for (dj, &wy_j) in wy.iter().enumerate() {
+                for (dk, &wz_k) in wz.iter().enumerate() {
+                    let idx = wrapped_index(
+                        i + di as isize - 1,
+                        j + dj as isize - 1,
+                        k + dk as isize - 1,
+                        n_isize,
+                        shear_shift_cells,
+                    );
+                    grid.density_contrast[idx] += p.mass * wx_i * wy_j * wz_k;
+                }
+            }
+        }
+    }
+
+    if mean_density > 1e-6 {
+        for val in &mut grid.density_contrast {
+            *val = (*val / mean_density) - 1.0;
+        }
+    }
+}
+
 /// Assigns particle mass to the grid using the Cloud-in-Cell (CIC) scheme.
 /// This method provides a smoother density field than Nearest Grid Point.
-pub fn assign_mass_cic(particles: &ParticleSet, grid: &mut Grid) {
+/// `cell_offset` shifts the assignment window by that many cells along
+/// each axis (0.0 for a normal assignment, 0.5 for the interlaced partner).
+/// `shear_shift_cells` offsets the y-index by that many cells for every
+/// box-width the x-index wraps by (0 under plain periodic wrapping; see
+/// `boundary::wrapped_index`).
+pub fn assign_mass_cic(particles: &ParticleSet, grid: &mut Grid, cell_offset: f32, shear_shift_cells: isize) {
     let n = grid.resolution;
     let n_f32 = n as f32;
     let cell_size = grid.box_size / n_f32;
@@ -20,9 +215,9 @@ pub fn assign_mass_cic(particles: &ParticleSet, grid: &mut Grid) {
     for p in &particles.particles {
         // Find the particle's position in grid coordinates.
         let pos_grid = [
-            p.position[0] * inv_cell_size,
-            p.position[1] * inv_cell_size,
-            p.position[2] * inv_cell_size,
+            p.position[0] * inv_cell_size - cell_offset,
+            p.position[1] * inv_cell_size - cell_offset,
+            p.position[2] * inv_cell_size - cell_offset,
         ];
 
         // Find the integer index of the "base" grid cell (the bottom-left-front corner).
@@ -45,13 +240,11 @@ pub fn assign_mass_cic(particles: &ParticleSet, grid: &mut Grid) {
         let w_011 = (1.0 - dx) * dy * dz;
         let w_111 = dx * dy * dz;
 
-        // Distribute the particle's mass to the 8 cells, handling periodic boundaries.
-        // We use isize and the modulo operator `%` to wrap around the grid edges.
+        // Distribute the particle's mass to the 8 cells, wrapping around the
+        // grid edges (and offsetting y by `shear_shift_cells` wherever the
+        // x-index wraps, for a shearing-sheet boundary).
         let n_isize = n as isize;
-        let idx = |x: isize, y: isize, z: isize| {
-            (((x.rem_euclid(n_isize)) * n_isize + y.rem_euclid(n_isize)) * n_isize + z.rem_euclid(n_isize))
-                as usize
-        };
+        let idx = |x: isize, y: isize, z: isize| wrapped_index(x, y, z, n_isize, shear_shift_cells);
 
         grid.density_contrast[idx(i, j, k)] += p.mass * w_000;
         grid.density_contrast[idx(i + 1, j, k)] += p.mass * w_100;
@@ -70,3 +263,151 @@ pub fn assign_mass_cic(particles: &ParticleSet, grid: &mut Grid) {
         }
     }
 }
+
+/// Assigns particle mass to the grid using the Triangular-Shaped-Cloud (TSC)
+/// scheme: each particle is spread over the 3 nearest cells per axis (27
+/// cells total), which smooths the density field more aggressively than CIC.
+/// `cell_offset` shifts the assignment window by that many cells along
+/// each axis (0.0 for a normal assignment, 0.5 for the interlaced partner).
+/// `shear_shift_cells` offsets the y-index by that many cells for every
+/// box-width the x-index wraps by (0 under plain periodic wrapping; see
+/// `boundary::wrapped_index`).
+pub fn assign_mass_tsc(particles: &ParticleSet, grid: &mut Grid, cell_offset: f32, shear_shift_cells: isize) {
+    let n = grid.resolution;
+    let n_f32 = n as f32;
+    let cell_size = grid.box_size / n_f32;
+    let inv_cell_size = 1.0 / cell_size;
+
+    let total_mass: f32 = particles.particles.iter().map(|p| p.mass).sum();
+    let total_cells = (n * n * n) as f32;
+    let mean_density = total_mass / total_cells;
+
+    let n_isize = n as isize;
+
+    for p in &particles.particles {
+        let pos_grid = [
+            p.position[0] * inv_cell_size - cell_offset,
+            p.position[1] * inv_cell_size - cell_offset,
+            p.position[2] * inv_cell_size - cell_offset,
+        ];
+
+        // Nearest cell centre per axis, and the three per-axis weights
+        // around it (outer two cells get 0.5*(1.5-|d|)^2, the centre cell
+        // gets 0.75 - d^2).
+        let (i, wx) = tsc_weights(pos_grid[0]);
+        let (j, wy) = tsc_weights(pos_grid[1]);
+        let (k, wz) = tsc_weights(pos_grid[2]);
+
+        for (di, &wx_i) in wx.iter().enumerate() {
+            for (dj, &wy_j) in wy.iter().enumerate() {
+                for (dk, &wz_k) in wz.iter().enumerate() {
+                    let idx = wrapped_index(
+                        i + di as isize - 1,
+                        j + dj as isize - 1,
+                        k + dk as isize - 1,
+                        n_isize,
+                        shear_shift_cells,
+                    );
+                    grid.density_contrast[idx] += p.mass * wx_i * wy_j * wz_k;
+                }
+            }
+        }
+    }
+
+    if mean_density > 1e-6 {
+        for val in &mut grid.density_contrast {
+            *val = (*val / mean_density) - 1.0;
+        }
+    }
+}
+
+/// Runs the full Particle-Mesh force pipeline in one call: assigns mass to
+/// `grid` with `scheme`, solves the Poisson equation in Fourier space via
+/// `fft_solver` (forward 3D FFT of the density contrast, `phi(k) =
+/// -delta(k)/k^2` with the DC mode zeroed, force components from `-i*k_i`
+/// ik-differentiation, inverse 3D FFT — see `FftSolver::solve_forces`,
+/// which deconvolves the squared assignment window `W(k)` so the
+/// assign-then-interpolate round trip doesn't bias small scales), scales
+/// by `poisson_normalization` (the `4*pi*G / mean_density` this
+/// simulation's internal units fold into the Green's function), and
+/// interpolates the result back onto `Particle::force` with the matching
+/// scheme. `grid`'s density is cleared first, so callers don't need to.
+/// Uses plain periodic wrapping; see `compute_pm_forces_with_boundary` for
+/// the shearing-sheet-aware version.
+pub fn compute_pm_forces(
+    particles: &mut ParticleSet,
+    grid: &mut Grid,
+    fft_solver: &mut FftSolver,
+    scheme: AssignmentScheme,
+    poisson_normalization: f32,
+) {
+    compute_pm_forces_with_boundary(
+        particles,
+        grid,
+        fft_solver,
+        scheme,
+        poisson_normalization,
+        BoundaryCondition::Periodic,
+        0.0,
+    )
+}
+
+/// Like `compute_pm_forces`, but assigns mass and interpolates forces under
+/// `boundary` evaluated at `time` instead of assuming plain periodic
+/// wrapping, so a `BoundaryCondition::ShearingSheet` patch gets consistent
+/// density and force fields across its sheared radial edge.
+pub fn compute_pm_forces_with_boundary(
+    particles: &mut ParticleSet,
+    grid: &mut Grid,
+    fft_solver: &mut FftSolver,
+    scheme: AssignmentScheme,
+    poisson_normalization: f32,
+    boundary: BoundaryCondition,
+    time: f32,
+) {
+    grid.clear_density();
+    assign_mass_with_boundary(particles, grid, scheme, boundary, time);
+
+    let (mut fx, mut fy, mut fz) = fft_solver.solve_forces(grid, scheme);
+    for (fx_i, (fy_i, fz_i)) in fx.iter_mut().zip(fy.iter_mut().zip(fz.iter_mut())) {
+        *fx_i *= poisson_normalization;
+        *fy_i *= poisson_normalization;
+        *fz_i *= poisson_normalization;
+    }
+
+    forces::interpolate_forces_to_particles_with_boundary(
+        particles, grid, &fx, &fy, &fz, scheme, boundary, time,
+    );
+}
+
+/// Rounds a grid coordinate to its nearest cell index and returns that index
+/// along with the TSC weights for the cell below it, itself, and above it.
+pub(crate) fn tsc_weights(pos_grid: f32) -> (isize, [f32; 3]) {
+    let i = pos_grid.round() as isize;
+    let d = pos_grid - i as f32;
+
+    let w_minus = 0.5 * (0.5 - d).powi(2);
+    let w_centre = 0.75 - d * d;
+    let w_plus = 0.5 * (0.5 + d).powi(2);
+
+    (i, [w_minus, w_centre, w_plus])
+}
+
+/// Floors a grid coordinate to its base cell index and returns that index
+/// along with the cubic B-spline (PCS) weights for the four cells it
+/// touches: `base - 1`, `base`, `base + 1`, `base + 2`. Derived from the
+/// standard cubic B-spline kernel `w(u) = (4 - 6u^2 + 3|u|^3)/6` for
+/// `|u|<1` and `(2-|u|)^3/6` for `1<=|u|<2`, evaluated at each node's
+/// distance from the particle (`1+t`, `t`, `1-t`, `2-t` for fractional
+/// offset `t` from `base`).
+pub(crate) fn pcs_weights(pos_grid: f32) -> (isize, [f32; 4]) {
+    let i = pos_grid.floor() as isize;
+    let t = pos_grid - i as f32;
+
+    let w_minus = (1.0 - t).powi(3) / 6.0;
+    let w_centre = (4.0 - 6.0 * t * t + 3.0 * t.powi(3)) / 6.0;
+    let w_plus = (4.0 - 6.0 * (1.0 - t).powi(2) + 3.0 * (1.0 - t).powi(3)) / 6.0;
+    let w_far = t.powi(3) / 6.0;
+
+    (i, [w_minus, w_centre, w_plus, w_far])
+}
@@ -1,7 +1,9 @@
+use super::boundary::BoundaryCondition;
+use super::fft_solver::FftSolver;
 use num_complex::Complex;
-use rand::Rng;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rand_distr::{Distribution, Normal};
-use rustfft::FftPlanner;
 
 pub struct Particle {
     pub position: [f32; 3],
@@ -15,6 +17,153 @@ pub struct ParticleSet {
     pub box_size: f32,
 }
 
+/// Draws a Gaussian random field directly in Fourier space as
+/// `delta_k = sqrt(P(k)/2) * (a + i*b)` with `a, b` unit Gaussian draws,
+/// enforcing the Hermitian symmetry `delta(-k) = conj(delta(k))` so the
+/// real-space density is real, and writes it into `solver`'s work buffer.
+/// Shared by `initialize_zeldovich` and `initialize_2lpt`, which both need
+/// the same first-order density field as their starting point.
+fn draw_gaussian_delta_k<F: Fn(f32) -> f32>(
+    solver: &mut FftSolver,
+    n: usize,
+    k_fundamental: f32,
+    power_spectrum: F,
+    rng: &mut StdRng,
+    normal: &Normal<f32>,
+    fold: impl Fn(usize) -> f32,
+    mirror_index: impl Fn(usize, usize, usize) -> usize,
+) {
+    let buffer = solver.buffer_mut();
+    for ix in 0..n {
+        for iy in 0..n {
+            for iz in 0..n {
+                let idx = (ix * n + iy) * n + iz;
+                let midx = mirror_index(ix, iy, iz);
+                if midx < idx {
+                    continue; // already set while visiting its mirror
+                }
+
+                let k_mag = (fold(ix).powi(2) + fold(iy).powi(2) + fold(iz).powi(2)).sqrt()
+                    * k_fundamental;
+                if k_mag < 1e-6 {
+                    buffer[idx] = Complex::new(0.0, 0.0); // no mean overdensity
+                    continue;
+                }
+
+                let amplitude = (power_spectrum(k_mag) / 2.0).max(0.0).sqrt();
+                let a = normal.sample(rng) as f32;
+                if midx == idx {
+                    // Self-conjugate mode (e.g. a Nyquist plane): must be
+                    // real, so it carries the full P(k) variance alone.
+                    buffer[idx] = Complex::new(amplitude * std::f32::consts::SQRT_2 * a, 0.0);
+                } else {
+                    let b = normal.sample(rng) as f32;
+                    buffer[idx] = Complex::new(amplitude * a, amplitude * b);
+                    buffer[midx] = buffer[idx].conj();
+                }
+            }
+        }
+    }
+}
+
+/// ik-differentiates a potential spectrum into a displacement field and
+/// inverse-transforms each axis back to real space:
+/// `Psi_i(k) = grad_sign * i*k_i * phi_k`. Pass `grad_sign = -1.0` for the
+/// first-order `Psi = -grad(phi)` convention used by `initialize_zeldovich`,
+/// or `+1.0` for the second-order `Psi = +grad(phi)` convention 2LPT uses
+/// (see `initialize_2lpt`). Leaves `solver`'s work buffer as scratch.
+fn displacement_from_potential(
+    solver: &mut FftSolver,
+    phi_k: &[Complex<f32>],
+    n: usize,
+    k_fundamental: f32,
+    grad_sign: f32,
+) -> [Vec<f32>; 3] {
+    let num_cells = n * n * n;
+    let normalization = 1.0 / num_cells as f32;
+    let mut disp = [
+        vec![0.0f32; num_cells],
+        vec![0.0f32; num_cells],
+        vec![0.0f32; num_cells],
+    ];
+    for (axis, disp_axis) in disp.iter_mut().enumerate() {
+        for i in 0..num_cells {
+            let (kx, ky, kz) = solver.k_vector(i, k_fundamental);
+            let k_i = [kx, ky, kz][axis];
+            let index_along_axis = [i / (n * n), (i / n) % n, i % n][axis];
+            // The Nyquist mode has no positive-frequency partner, so its
+            // derivative is set to zero to keep the result real.
+            let k_i = if index_along_axis == n / 2 { 0.0 } else { k_i };
+            solver.buffer_mut()[i] = phi_k[i] * Complex::new(0.0, grad_sign * k_i);
+        }
+        solver.apply_transform(false);
+        let buffer = solver.buffer_mut();
+        for (i, v) in disp_axis.iter_mut().enumerate() {
+            *v = buffer[i].re * normalization;
+        }
+    }
+    disp
+}
+
+/// Computes one second derivative `phi_{,ij}` of a potential spectrum in
+/// real space: multiplies `phi_k` by `-k_i*k_j` and inverse-transforms,
+/// zeroing either factor's contribution at its axis's Nyquist mode (no
+/// positive-frequency partner there, same guard as `displacement_from_potential`)
+/// so the result comes out real. Used by `initialize_2lpt` to build the
+/// six independent entries of the tidal tensor `phi^{(1)}_{,ij}`.
+fn second_derivative_real(
+    solver: &mut FftSolver,
+    phi_k: &[Complex<f32>],
+    n: usize,
+    k_fundamental: f32,
+    axis_i: usize,
+    axis_j: usize,
+) -> Vec<f32> {
+    let num_cells = n * n * n;
+    let normalization = 1.0 / num_cells as f32;
+
+    for idx in 0..num_cells {
+        let (kx, ky, kz) = solver.k_vector(idx, k_fundamental);
+        let k = [kx, ky, kz];
+        let index_along = [idx / (n * n), (idx / n) % n, idx % n];
+
+        let k_i = if index_along[axis_i] == n / 2 { 0.0 } else { k[axis_i] };
+        let k_j = if index_along[axis_j] == n / 2 { 0.0 } else { k[axis_j] };
+
+        solver.buffer_mut()[idx] = phi_k[idx] * Complex::new(-k_i * k_j, 0.0);
+    }
+    solver.apply_transform(false);
+
+    let buffer = solver.buffer_mut();
+    let mut out = vec![0.0f32; num_cells];
+    for (i, v) in out.iter_mut().enumerate() {
+        *v = buffer[i].re * normalization;
+    }
+    out
+}
+
+/// Solves a Poisson-type equation `sign * k^2 * phi_k = source_k` for the
+/// potential spectrum, given `source_k` already sitting in `solver`'s work
+/// buffer (forward-transformed). Zeroes the `k=0` (DC) mode, matching the
+/// existing k≈0 guard used everywhere else a potential is solved for. Used
+/// for both the first-order potential (`sign = -1.0`, `k^2 phi = -delta`)
+/// and the 2LPT second-order potential (`sign = 1.0`, `k^2 phi = delta^(2)`).
+fn potential_from_spectrum(solver: &mut FftSolver, n: usize, k_fundamental: f32, sign: f32) -> Vec<Complex<f32>> {
+    let num_cells = n * n * n;
+    let mut phi_k = vec![Complex::new(0.0, 0.0); num_cells];
+    for i in 0..num_cells {
+        let (kx, ky, kz) = solver.k_vector(i, k_fundamental);
+        let k_squared = kx * kx + ky * ky + kz * kz;
+        let source_k = solver.buffer_mut()[i];
+        phi_k[i] = if k_squared > 1e-6 {
+            sign * source_k / k_squared
+        } else {
+            Complex::new(0.0, 0.0)
+        };
+    }
+    phi_k
+}
+
 impl ParticleSet {
     pub fn new() -> Self {
         Self {
@@ -23,198 +172,211 @@ impl ParticleSet {
         }
     }
 
-    /// Initializes particle positions and velocities using the Zel'dovich Approximation.
-    /// This is a physically motivated method based on linear perturbation theory.
-    /// Uses proper 3D FFT via batched 1D transforms along each axis.
-    pub fn initialize_zeldovich(&mut self, grid_res: usize, box_size: f32) {
+    /// Initializes particle positions and velocities using the Zel'dovich
+    /// Approximation driven by a user-supplied power spectrum `P(k)`.
+    ///
+    /// A Gaussian random field is drawn directly in Fourier space as
+    /// `delta_k = sqrt(P(k)/2) * (a + i*b)` with `a, b` unit Gaussian draws,
+    /// enforcing the Hermitian symmetry `delta(-k) = conj(delta(k))` so the
+    /// real-space density is real. The displacement potential `phi` solves
+    /// `k^2 phi_k = -delta_k`, and particles are displaced off a uniform
+    /// grid by `x = q + D+ * Psi` with `Psi = -grad(phi)`, `v = f*H * Psi`
+    /// (`velocity_prefactor` standing in for `f*H` in this simulation's
+    /// internal units). This reuses `FftSolver`'s axis-by-axis transform
+    /// machinery rather than planning its own.
+    pub fn initialize_zeldovich(
+        &mut self,
+        grid_res: usize,
+        box_size: f32,
+        power_spectrum: impl Fn(f32) -> f32,
+        seed: u64,
+        growth_factor: f32,
+        velocity_prefactor: f32,
+    ) {
         self.box_size = box_size;
         let n = grid_res;
-        let num_particles = n * n * n;
-        self.particles = Vec::with_capacity(num_particles);
-        let mut rng = rand::rng();
-
-        // Set up 1D FFT plans for 3D transform (batched along each axis)
-        let mut planner = FftPlanner::new();
-        let fwd_plan = planner.plan_fft_forward(n);
-        let inv_plan = planner.plan_fft_inverse(n);
+        let num_cells = n * n * n;
+        self.particles = Vec::with_capacity(num_cells);
 
-        // Generate Gaussian random field in real space, then FFT to get density_k
-        let mut density = vec![Complex::new(0.0, 0.0); num_particles];
-        
-        // Initialize with Gaussian random values
+        let mut rng = StdRng::seed_from_u64(seed);
         let normal = Normal::new(0.0, 1.0).unwrap();
-        for val in &mut density {
-            *val = Complex::new(normal.sample(&mut rng) as f32, 0.0);
-        }
+        let k_fundamental = 2.0 * std::f32::consts::PI / box_size;
 
-        // Forward 3D FFT: apply 1D FFT along each dimension
-        // Step 1: FFT along z-axis (innermost, contiguous)
-        for ix in 0..n {
-            for iy in 0..n {
-                let offset = (ix * n + iy) * n;
-                let mut slice: Vec<Complex<f32>> = density[offset..offset + n].to_vec();
-                fwd_plan.process(&mut slice);
-                density[offset..offset + n].copy_from_slice(&slice);
-            }
-        }
+        let fold = |i: usize| -> f32 {
+            if i > n / 2 { i as f32 - n as f32 } else { i as f32 }
+        };
+        let mirror_index = |ix: usize, iy: usize, iz: usize| -> usize {
+            (((n - ix) % n) * n + (n - iy) % n) * n + (n - iz) % n
+        };
 
-        // Step 2: FFT along y-axis
-        for ix in 0..n {
-            for iz in 0..n {
-                let mut slice = vec![Complex::new(0.0, 0.0); n];
-                for iy in 0..n {
-                    slice[iy] = density[(ix * n + iy) * n + iz];
-                }
-                fwd_plan.process(&mut slice);
-                for iy in 0..n {
-                    density[(ix * n + iy) * n + iz] = slice[iy];
-                }
-            }
-        }
+        let mut solver = FftSolver::new(n);
 
-        // Step 3: FFT along x-axis
-        for iy in 0..n {
-            for iz in 0..n {
-                let mut slice = vec![Complex::new(0.0, 0.0); n];
-                for ix in 0..n {
-                    slice[ix] = density[(ix * n + iy) * n + iz];
-                }
-                fwd_plan.process(&mut slice);
-                for ix in 0..n {
-                    density[(ix * n + iy) * n + iz] = slice[ix];
-                }
-            }
-        }
+        // Draw delta_k directly in Fourier space, one conjugate pair at a
+        // time, so the real-space field it transforms to is guaranteed real.
+        draw_gaussian_delta_k(&mut solver, n, k_fundamental, power_spectrum, &mut rng, &normal, fold, mirror_index);
 
-        // Now density contains density_k in Fourier space
-        // Apply power spectrum weighting P(k) ~ k^(-1.5) for CDM-like clustering
-        let k_fundamental = 2.0 * std::f32::consts::PI / box_size;
-        
-        for ix in 0..n {
-            for iy in 0..n {
-                for iz in 0..n {
-                    let kx = if ix > n / 2 { ix as i32 - n as i32 } else { ix as i32 } as f32 * k_fundamental;
-                    let ky = if iy > n / 2 { iy as i32 - n as i32 } else { iy as i32 } as f32 * k_fundamental;
-                    let kz = if iz > n / 2 { iz as i32 - n as i32 } else { iz as i32 } as f32 * k_fundamental;
-                    
-                    let k_mag_sq = kx * kx + ky * ky + kz * kz;
-                    let idx = (ix * n + iy) * n + iz;
-                    
-                    if k_mag_sq < 1e-6 {
-                        // DC mode: set to zero (no mean overdensity)
-                        density[idx] = Complex::new(0.0, 0.0);
-                    } else {
-                        // Apply P(k) ~ k^(-1.5) weighting for realistic structure
-                        let p_k = k_mag_sq.powf(-0.75); // sqrt(P(k))
-                        density[idx] *= p_k;
-                    }
-                }
-            }
-        }
+        // Solve the displacement potential: k^2 * phi_k = -delta_k.
+        let phi_k = potential_from_spectrum(&mut solver, n, k_fundamental, -1.0);
 
-        // Compute displacement field: Ψ_i(k) = i * k_i * δ(k) / k²
-        let mut disp_x = vec![Complex::new(0.0, 0.0); num_particles];
-        let mut disp_y = vec![Complex::new(0.0, 0.0); num_particles];
-        let mut disp_z = vec![Complex::new(0.0, 0.0); num_particles];
+        // Displacement field Psi_i(k) = -i*k_i*phi_k (ik-differentiation,
+        // same convention as FftSolver::solve_forces), inverse-transformed
+        // axis by axis back to real space.
+        let disp = displacement_from_potential(&mut solver, &phi_k, n, k_fundamental, -1.0);
 
+        let cell_size = box_size / n as f32;
         for ix in 0..n {
             for iy in 0..n {
                 for iz in 0..n {
-                    let kx = if ix > n / 2 { ix as i32 - n as i32 } else { ix as i32 } as f32 * k_fundamental;
-                    let ky = if iy > n / 2 { iy as i32 - n as i32 } else { iy as i32 } as f32 * k_fundamental;
-                    let kz = if iz > n / 2 { iz as i32 - n as i32 } else { iz as i32 } as f32 * k_fundamental;
-                    
-                    let k_mag_sq = kx * kx + ky * ky + kz * kz;
                     let idx = (ix * n + iy) * n + iz;
-                    
-                    if k_mag_sq > 1e-6 {
-                        let factor = Complex::new(0.0, -1.0) / k_mag_sq; // -i/k² (note sign for IFFT convention)
-                        disp_x[idx] = density[idx] * kx * factor;
-                        disp_y[idx] = density[idx] * ky * factor;
-                        disp_z[idx] = density[idx] * kz * factor;
-                    }
+
+                    // Lagrangian position (regular grid)
+                    let q = [
+                        (ix as f32 + 0.5) * cell_size,
+                        (iy as f32 + 0.5) * cell_size,
+                        (iz as f32 + 0.5) * cell_size,
+                    ];
+
+                    let psi = [disp[0][idx], disp[1][idx], disp[2][idx]];
+
+                    // Eulerian position: x = q + D+ * Psi(q)
+                    let position = [
+                        (q[0] + growth_factor * psi[0]).rem_euclid(box_size),
+                        (q[1] + growth_factor * psi[1]).rem_euclid(box_size),
+                        (q[2] + growth_factor * psi[2]).rem_euclid(box_size),
+                    ];
+
+                    // Peculiar velocity v = f*H * Psi(q)
+                    let velocity = [
+                        velocity_prefactor * psi[0],
+                        velocity_prefactor * psi[1],
+                        velocity_prefactor * psi[2],
+                    ];
+
+                    self.particles.push(Particle {
+                        position,
+                        velocity,
+                        force: [0.0, 0.0, 0.0],
+                        mass: 1.0,
+                    });
                 }
             }
         }
+    }
 
-        // Inverse 3D FFT for each displacement component
-        for disp in [&mut disp_x, &mut disp_y, &mut disp_z] {
-            // IFFT along x-axis
-            for iy in 0..n {
-                for iz in 0..n {
-                    let mut slice = vec![Complex::new(0.0, 0.0); n];
-                    for ix in 0..n {
-                        slice[ix] = disp[(ix * n + iy) * n + iz];
-                    }
-                    inv_plan.process(&mut slice);
-                    for ix in 0..n {
-                        disp[(ix * n + iy) * n + iz] = slice[ix];
-                    }
-                }
-            }
+    /// Initializes particle positions and velocities using second-order
+    /// Lagrangian perturbation theory (2LPT), which extends
+    /// `initialize_zeldovich`'s first-order displacement `Psi^(1)` with a
+    /// second-order correction `Psi^(2)` that reduces the transients the
+    /// pure Zel'dovich approximation leaves on small scales.
+    ///
+    /// The first-order potential `phi^(1)_k = -delta_k/k^2` is the same one
+    /// `initialize_zeldovich` computes. Its six second derivatives
+    /// `phi^(1)_{,ij}` (via `second_derivative_real`) build the 2LPT source
+    /// `delta^(2)(q) = sum_{i<j}(phi^(1)_{,ii}*phi^(1)_{,jj} - (phi^(1)_{,ij})^2)`
+    /// in real space. Forward-transforming that source and solving
+    /// `phi^(2)_k = delta^(2)_k/k^2` (note: no leading minus, unlike the
+    /// first-order equation) gives the second-order potential, whose
+    /// displacement is `Psi^(2) = +grad(phi^(2))` (also no minus).
+    ///
+    /// The final position is `q + D+ * Psi^(1) + D+^2 * (3/7) * Psi^(2)`,
+    /// the `3/7` being the EdS second-order growth ratio `D2 ~= -3/7 * D1^2`
+    /// (the sign is already folded into `phi^(2)`'s convention above).
+    /// Velocities scale the same way, with `velocity_prefactor` and
+    /// `velocity_prefactor_2` standing in for `f1*H` and `f2*H` respectively:
+    /// `v = f1*H*Psi^(1) + 2*(3/7)*f2*H*Psi^(2)`.
+    pub fn initialize_2lpt(
+        &mut self,
+        grid_res: usize,
+        box_size: f32,
+        power_spectrum: impl Fn(f32) -> f32,
+        seed: u64,
+        growth_factor: f32,
+        velocity_prefactor: f32,
+        velocity_prefactor_2: f32,
+    ) {
+        self.box_size = box_size;
+        let n = grid_res;
+        let num_cells = n * n * n;
+        self.particles = Vec::with_capacity(num_cells);
 
-            // IFFT along y-axis
-            for ix in 0..n {
-                for iz in 0..n {
-                    let mut slice = vec![Complex::new(0.0, 0.0); n];
-                    for iy in 0..n {
-                        slice[iy] = disp[(ix * n + iy) * n + iz];
-                    }
-                    inv_plan.process(&mut slice);
-                    for iy in 0..n {
-                        disp[(ix * n + iy) * n + iz] = slice[iy];
-                    }
-                }
-            }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let normal = Normal::new(0.0, 1.0).unwrap();
+        let k_fundamental = 2.0 * std::f32::consts::PI / box_size;
 
-            // IFFT along z-axis
-            for ix in 0..n {
-                for iy in 0..n {
-                    let offset = (ix * n + iy) * n;
-                    let mut slice: Vec<Complex<f32>> = disp[offset..offset + n].to_vec();
-                    inv_plan.process(&mut slice);
-                    disp[offset..offset + n].copy_from_slice(&slice);
-                }
+        let fold = |i: usize| -> f32 {
+            if i > n / 2 { i as f32 - n as f32 } else { i as f32 }
+        };
+        let mirror_index = |ix: usize, iy: usize, iz: usize| -> usize {
+            (((n - ix) % n) * n + (n - iy) % n) * n + (n - iz) % n
+        };
+
+        let mut solver = FftSolver::new(n);
+
+        // First-order density field and potential, exactly as in
+        // initialize_zeldovich.
+        draw_gaussian_delta_k(&mut solver, n, k_fundamental, power_spectrum, &mut rng, &normal, fold, mirror_index);
+        let phi1_k = potential_from_spectrum(&mut solver, n, k_fundamental, -1.0);
+        let disp1 = displacement_from_potential(&mut solver, &phi1_k, n, k_fundamental, -1.0);
+
+        // Tidal tensor phi^(1)_{,ij}, the six second derivatives of the
+        // first-order potential, in real space.
+        let phi_xx = second_derivative_real(&mut solver, &phi1_k, n, k_fundamental, 0, 0);
+        let phi_yy = second_derivative_real(&mut solver, &phi1_k, n, k_fundamental, 1, 1);
+        let phi_zz = second_derivative_real(&mut solver, &phi1_k, n, k_fundamental, 2, 2);
+        let phi_xy = second_derivative_real(&mut solver, &phi1_k, n, k_fundamental, 0, 1);
+        let phi_xz = second_derivative_real(&mut solver, &phi1_k, n, k_fundamental, 0, 2);
+        let phi_yz = second_derivative_real(&mut solver, &phi1_k, n, k_fundamental, 1, 2);
+
+        // 2LPT source: delta^(2)(q) = sum_{i<j}(phi_ii*phi_jj - phi_ij^2).
+        let mut delta2 = vec![0.0f32; num_cells];
+        for idx in 0..num_cells {
+            delta2[idx] = phi_xx[idx] * phi_yy[idx] + phi_xx[idx] * phi_zz[idx]
+                + phi_yy[idx] * phi_zz[idx]
+                - phi_xy[idx].powi(2)
+                - phi_xz[idx].powi(2)
+                - phi_yz[idx].powi(2);
+        }
+
+        // Forward-transform the source and solve phi^(2)_k = delta^(2)_k/k^2
+        // (positive sign, unlike the first-order equation).
+        {
+            let buffer = solver.buffer_mut();
+            for (i, v) in delta2.iter().enumerate() {
+                buffer[i] = Complex::new(*v, 0.0);
             }
         }
+        solver.apply_transform(true);
+        let phi2_k = potential_from_spectrum(&mut solver, n, k_fundamental, 1.0);
+        let disp2 = displacement_from_potential(&mut solver, &phi2_k, n, k_fundamental, 1.0);
 
-        // Normalization and particle placement
-        let normalization = 1.0 / (num_particles as f32);
         let cell_size = box_size / n as f32;
-        let displacement_scale = box_size * 0.05; // Scale displacements to ~5% of box
-        let velocity_scale = 0.02; // Small initial velocities
-
+        let growth_factor_2 = growth_factor * growth_factor * (3.0 / 7.0);
+        let velocity_prefactor_2 = velocity_prefactor_2 * 2.0 * (3.0 / 7.0);
         for ix in 0..n {
             for iy in 0..n {
                 for iz in 0..n {
                     let idx = (ix * n + iy) * n + iz;
-                    
-                    // Lagrangian position (regular grid)
+
                     let q = [
                         (ix as f32 + 0.5) * cell_size,
                         (iy as f32 + 0.5) * cell_size,
                         (iz as f32 + 0.5) * cell_size,
                     ];
 
-                    // Zel'dovich displacement
-                    let psi = [
-                        disp_x[idx].re * normalization * displacement_scale,
-                        disp_y[idx].re * normalization * displacement_scale,
-                        disp_z[idx].re * normalization * displacement_scale,
-                    ];
+                    let psi1 = [disp1[0][idx], disp1[1][idx], disp1[2][idx]];
+                    let psi2 = [disp2[0][idx], disp2[1][idx], disp2[2][idx]];
 
-                    // Eulerian position: x = q + Ψ(q)
+                    // Eulerian position: x = q + D+ * Psi^(1) + D+^2 * (3/7) * Psi^(2)
                     let position = [
-                        (q[0] + psi[0]).rem_euclid(box_size),
-                        (q[1] + psi[1]).rem_euclid(box_size),
-                        (q[2] + psi[2]).rem_euclid(box_size),
+                        (q[0] + growth_factor * psi1[0] + growth_factor_2 * psi2[0]).rem_euclid(box_size),
+                        (q[1] + growth_factor * psi1[1] + growth_factor_2 * psi2[1]).rem_euclid(box_size),
+                        (q[2] + growth_factor * psi1[2] + growth_factor_2 * psi2[2]).rem_euclid(box_size),
                     ];
 
-                    // Peculiar velocity v = H*f*Ψ (approximated as v ∝ Ψ)
                     let velocity = [
-                        psi[0] * velocity_scale,
-                        psi[1] * velocity_scale,
-                        psi[2] * velocity_scale,
+                        velocity_prefactor * psi1[0] + velocity_prefactor_2 * psi2[0],
+                        velocity_prefactor * psi1[1] + velocity_prefactor_2 * psi2[1],
+                        velocity_prefactor * psi1[2] + velocity_prefactor_2 * psi2[2],
                     ];
 
                     self.particles.push(Particle {
@@ -319,28 +481,79 @@ impl ParticleSet {
         }
     }
 
-    /// Kick-drift-kick leapfrog integration.
-    pub fn integrate(&mut self, dt: f32) {
+    /// Half-kick: advances velocities by `dt/2` using the currently-stored
+    /// force. A full KDK leapfrog step calls this once before `drift` (using
+    /// the force at the old positions) and once after (using the force
+    /// recomputed at the new positions), making the integrator symplectic
+    /// and time-reversible.
+    pub fn half_kick(&mut self, dt: f32) {
         let half_dt = 0.5 * dt;
-
         for p in &mut self.particles {
             p.velocity[0] += p.force[0] * half_dt;
             p.velocity[1] += p.force[1] * half_dt;
             p.velocity[2] += p.force[2] * half_dt;
+        }
+    }
 
+    /// Drift: advances positions by a full step `dt` using the current
+    /// (half-kicked) velocity, wrapping across the periodic box.
+    pub fn drift(&mut self, dt: f32) {
+        for p in &mut self.particles {
             p.position[0] = (p.position[0] + p.velocity[0] * dt).rem_euclid(self.box_size);
             p.position[1] = (p.position[1] + p.velocity[1] * dt).rem_euclid(self.box_size);
             p.position[2] = (p.position[2] + p.velocity[2] * dt).rem_euclid(self.box_size);
         }
     }
 
-    /// Complete kick after force recalculation.
-    pub fn kick(&mut self, dt: f32) {
-        let half_dt = 0.5 * dt;
+    /// Half-kick in comoving coordinates: advances velocities using the
+    /// currently-stored force scaled by `kick_factor`, the a-dependent
+    /// prefactor `integral da/(a*H(a))` (see `Cosmology::kick_factor`) in
+    /// place of `half_kick`'s fixed `dt/2`. `run_simulation_cosmological`
+    /// calls it with the kick factor of each half of a scale-factor step
+    /// `da`, once before and once after `drift_cosmological`, the same way
+    /// `run_simulation`'s fixed-`dt` KDK splits `half_kick` around `drift`.
+    pub fn kick_cosmological(&mut self, kick_factor: f32) {
         for p in &mut self.particles {
-            p.velocity[0] += p.force[0] * half_dt;
-            p.velocity[1] += p.force[1] * half_dt;
-            p.velocity[2] += p.force[2] * half_dt;
+            p.velocity[0] += p.force[0] * kick_factor;
+            p.velocity[1] += p.force[1] * kick_factor;
+            p.velocity[2] += p.force[2] * kick_factor;
+        }
+    }
+
+    /// Drift in comoving coordinates: advances positions using the current
+    /// velocity scaled by `drift_factor`, the a-dependent prefactor
+    /// `integral da/(a^2*H(a))` (see `Cosmology::drift_factor`) in place of
+    /// `drift`'s fixed `dt`.
+    pub fn drift_cosmological(&mut self, drift_factor: f32) {
+        for p in &mut self.particles {
+            p.position[0] = (p.position[0] + p.velocity[0] * drift_factor).rem_euclid(self.box_size);
+            p.position[1] = (p.position[1] + p.velocity[1] * drift_factor).rem_euclid(self.box_size);
+            p.position[2] = (p.position[2] + p.velocity[2] * drift_factor).rem_euclid(self.box_size);
+        }
+    }
+
+    /// Drift under `boundary` evaluated at `time`: advances positions by
+    /// `dt` exactly like `drift`, but for `BoundaryCondition::ShearingSheet`
+    /// also tracks how many box-widths each particle's x-position crosses
+    /// and, per crossing, offsets its y-position by `boundary.delta_y` (the
+    /// accumulated azimuthal shear) and its y-velocity by the background
+    /// shear flow `v_y = -q * Omega * x`'s jump across that offset (see
+    /// `BoundaryCondition::shear_velocity`). Reduces to `drift` exactly when
+    /// `boundary` is `Periodic`.
+    pub fn drift_with_boundary(&mut self, dt: f32, time: f32, boundary: &BoundaryCondition) {
+        let delta_y = boundary.delta_y(self.box_size, time);
+        let shear_velocity = boundary.shear_velocity(self.box_size);
+
+        for p in &mut self.particles {
+            let new_x = p.position[0] + p.velocity[0] * dt;
+            let x_wraps = new_x.div_euclid(self.box_size);
+
+            p.position[0] = new_x.rem_euclid(self.box_size);
+            p.position[1] = (p.position[1] + p.velocity[1] * dt + x_wraps * delta_y)
+                .rem_euclid(self.box_size);
+            p.position[2] = (p.position[2] + p.velocity[2] * dt).rem_euclid(self.box_size);
+
+            p.velocity[1] -= x_wraps * shear_velocity;
         }
     }
 
@@ -379,3 +592,60 @@ impl ParticleSet {
         map
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::power_spectrum::{measure_power_spectrum_from_particles, PowerSpectrum};
+
+    /// Generates Zel'dovich displacements from a power-law `P(k)`, CIC-assigns
+    /// the resulting particles back onto a grid, and checks that the measured
+    /// `P(k)` matches the input spectrum within sampling (cosmic-variance)
+    /// noise. This is the one check that the IC generator's Fourier-space
+    /// machinery (`draw_gaussian_delta_k`, `potential_from_spectrum`,
+    /// `displacement_from_potential`) actually produces the spectrum it was
+    /// asked for, rather than e.g. a consistently mis-normalized one.
+    #[test]
+    fn zeldovich_displacement_reproduces_input_power_spectrum() {
+        let grid_res = 24;
+        let box_size = 100.0f32;
+        let power_spectrum = PowerSpectrum::PowerLaw {
+            amplitude: 5.0e4,
+            index: 1.5,
+        };
+
+        let mut particles = ParticleSet::new();
+        particles.initialize_zeldovich(
+            grid_res,
+            box_size,
+            |k| power_spectrum.eval(k),
+            42,
+            1.0,
+            1.0,
+        );
+
+        let (k_centers, p_measured) = measure_power_spectrum_from_particles(&particles, grid_res);
+
+        // Skip the lowest k (only a handful of modes per shell, so cosmic
+        // variance dominates) and the highest k (nonlinear shell-crossing
+        // and grid discreteness bite there); the middle of the range is
+        // where a mis-normalized spectrum would show up cleanly.
+        let mid_range = k_centers.len() / 4..(3 * k_centers.len() / 4);
+        assert!(!mid_range.is_empty(), "expected enough k bins to sample a mid-range");
+
+        for i in mid_range {
+            let k = k_centers[i];
+            let expected = power_spectrum.eval(k);
+            let measured = p_measured[i];
+            let ratio = measured / expected;
+            assert!(
+                (0.3..3.0).contains(&ratio),
+                "k={}: measured P(k)={} too far from input P(k)={} (ratio {})",
+                k,
+                measured,
+                expected,
+                ratio
+            );
+        }
+    }
+}
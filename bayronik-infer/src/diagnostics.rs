@@ -0,0 +1,329 @@
+//! Physics diagnostics: the 2-D isotropic power spectrum, the baryonic
+//! boost `P_out/P_in` it implies, and the log-space pixel-value PDF of the
+//! input/output maps, rendered as a fourth `ratatui` panel. This is what
+//! makes `driver::run_inference`'s mean/std rescaling of the N-body map onto
+//! CAMELS statistics verifiable in-app, instead of only via a one-off
+//! console print of mean/std.
+
+use num_complex::Complex;
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    symbols,
+    widgets::{Axis, Block, Borders, Chart, Dataset, GraphType, Widget},
+};
+use rustfft::FftPlanner;
+use tch::Tensor;
+
+/// Extracts a tensor's data as flat row-major `(side, values)`, squeezing
+/// down to its trailing dimensions. Returns `None` for anything that
+/// doesn't squeeze to a square 2D map.
+fn tensor_to_2d_data(tensor: &Tensor) -> Option<(usize, Vec<f32>)> {
+    let squeezed = tensor.squeeze().contiguous();
+    let dims = squeezed.size();
+    if dims.len() != 2 || dims[0] != dims[1] {
+        return None;
+    }
+    let side = dims[0] as usize;
+    let mut data = vec![0.0f32; side * side];
+    squeezed.copy_data(&mut data, side * side);
+    Some((side, data))
+}
+
+/// Computes the 2-D isotropic power spectrum of `tensor` (expected square,
+/// e.g. a 256x256 log-space density map): a separable row-then-column FFT,
+/// the same per-axis approach `bayronik_core`'s `FftSolver` uses for its 3D
+/// transform, followed by radial binning of `|F(k)|^2` into one shell per
+/// fundamental frequency. `k` is the integer shell index, i.e. cycles per
+/// map side, rather than a physical wavenumber, since a rendered map carries
+/// no box-size metadata. Returns
+/// `(k_centers, p_of_k)` for shells containing at least one mode, or empty
+/// vectors if `tensor` isn't square 2D data.
+pub fn power_spectrum_2d(tensor: &Tensor) -> (Vec<f32>, Vec<f32>) {
+    let (side, data) = match tensor_to_2d_data(tensor) {
+        Some(v) => v,
+        None => return (Vec::new(), Vec::new()),
+    };
+
+    let mut planner = FftPlanner::new();
+    let fft = planner.plan_fft_forward(side);
+
+    let mut buffer: Vec<Complex<f32>> = data.iter().map(|&v| Complex::new(v, 0.0)).collect();
+    let mut scratch = vec![Complex::new(0.0, 0.0); side];
+
+    for row in 0..side {
+        fft.process(&mut buffer[row * side..(row + 1) * side]);
+    }
+    for col in 0..side {
+        for row in 0..side {
+            scratch[row] = buffer[row * side + col];
+        }
+        fft.process(&mut scratch);
+        for row in 0..side {
+            buffer[row * side + col] = scratch[row];
+        }
+    }
+
+    let num_bins = side / 2;
+    let mut shell_sum = vec![0.0f64; num_bins];
+    let mut shell_count = vec![0u32; num_bins];
+
+    for row in 0..side {
+        let ky = if row <= side / 2 {
+            row as f32
+        } else {
+            row as f32 - side as f32
+        };
+        for col in 0..side {
+            let kx = if col <= side / 2 {
+                col as f32
+            } else {
+                col as f32 - side as f32
+            };
+            let k_mag = (kx * kx + ky * ky).sqrt();
+            if k_mag < 1e-6 {
+                continue; // skip the DC mode
+            }
+            let bin = k_mag.round() as usize;
+            if bin == 0 || bin >= num_bins {
+                continue;
+            }
+            shell_sum[bin] += buffer[row * side + col].norm_sqr() as f64;
+            shell_count[bin] += 1;
+        }
+    }
+
+    let mut k_centers = Vec::new();
+    let mut p_of_k = Vec::new();
+    for bin in 1..num_bins {
+        if shell_count[bin] == 0 {
+            continue;
+        }
+        k_centers.push(bin as f32);
+        p_of_k.push((shell_sum[bin] / shell_count[bin] as f64) as f32);
+    }
+    (k_centers, p_of_k)
+}
+
+/// Bins `tensor`'s values (already in log1p-space, matching `App`'s
+/// `input_map`/`output_map`) into `num_bins` equal-width bins spanning its
+/// own min/max, returning `(bin_centers, density)` where `density` is the
+/// fraction of pixels falling in that bin. The standard 1-point PDF check
+/// for whether two fields share marginal statistics, independent of their
+/// spatial structure (which the power spectrum instead captures).
+pub fn log_pdf_histogram(tensor: &Tensor, num_bins: usize) -> (Vec<f32>, Vec<f32>) {
+    let flat = tensor.contiguous().view([-1]);
+    let n = flat.numel();
+    let mut data = vec![0.0f32; n];
+    flat.copy_data(&mut data, n);
+
+    let data_min = data.iter().cloned().fold(f32::INFINITY, f32::min);
+    let data_max = data.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let range = if data_max > data_min { data_max - data_min } else { 1.0 };
+    let bin_width = range / num_bins as f32;
+
+    let mut counts = vec![0u64; num_bins];
+    for &v in &data {
+        let bin = (((v - data_min) / bin_width) as usize).min(num_bins - 1);
+        counts[bin] += 1;
+    }
+
+    let bin_centers = (0..num_bins)
+        .map(|i| data_min + (i as f32 + 0.5) * bin_width)
+        .collect();
+    let density = counts.iter().map(|&c| c as f32 / n as f32).collect();
+    (bin_centers, density)
+}
+
+/// The fourth panel, split into three: input-vs-output P(k) (log-log), the
+/// baryonic boost `P_out/P_in` that ratio implies, and the log-space pixel
+/// PDF. Computed fresh from `input_map`/`output_map` on every render, since
+/// the maps only change on navigation or a completed `Driver` job, not every
+/// tick.
+pub struct DiagnosticsWidget<'a> {
+    input_map: &'a Tensor,
+    output_map: &'a Tensor,
+}
+
+impl<'a> DiagnosticsWidget<'a> {
+    pub fn new(input_map: &'a Tensor, output_map: &'a Tensor) -> Self {
+        Self {
+            input_map,
+            output_map,
+        }
+    }
+}
+
+impl Widget for DiagnosticsWidget<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(34),
+                Constraint::Percentage(33),
+                Constraint::Percentage(33),
+            ])
+            .split(area);
+
+        render_power_spectrum_chart(self.input_map, self.output_map, columns[0], buf);
+        render_boost_chart(self.input_map, self.output_map, columns[1], buf);
+        render_pdf_chart(self.input_map, self.output_map, columns[2], buf);
+    }
+}
+
+/// Converts a `(k, P(k))` series to log10-log10 `(x, y)` points, dropping
+/// any non-positive `P(k)` a given bin can't have a log taken of.
+fn to_log_log_points(k: &[f32], p_of_k: &[f32]) -> Vec<(f64, f64)> {
+    k.iter()
+        .zip(p_of_k.iter())
+        .filter(|(_, &p)| p > 0.0)
+        .map(|(&k, &p)| (k.log10() as f64, p.log10() as f64))
+        .collect()
+}
+
+fn axis_bounds(points: &[(f64, f64)], pick: impl Fn(&(f64, f64)) -> f64) -> [f64; 2] {
+    let values = points.iter().map(pick);
+    let min = values.clone().fold(f64::INFINITY, f64::min);
+    let max = values.fold(f64::NEG_INFINITY, f64::max);
+    if min.is_finite() && max.is_finite() && max > min {
+        [min, max]
+    } else {
+        [0.0, 1.0]
+    }
+}
+
+fn render_power_spectrum_chart(input_map: &Tensor, output_map: &Tensor, area: Rect, buf: &mut Buffer) {
+    let (k_in, p_in) = power_spectrum_2d(input_map);
+    let (k_out, p_out) = power_spectrum_2d(output_map);
+    let input_points = to_log_log_points(&k_in, &p_in);
+    let output_points = to_log_log_points(&k_out, &p_out);
+
+    let all_points: Vec<(f64, f64)> = input_points.iter().chain(output_points.iter()).copied().collect();
+    let x_bounds = axis_bounds(&all_points, |p| p.0);
+    let y_bounds = axis_bounds(&all_points, |p| p.1);
+
+    let datasets = vec![
+        Dataset::default()
+            .name("input")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Blue))
+            .data(&input_points),
+        Dataset::default()
+            .name("output")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Green))
+            .data(&output_points),
+    ];
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("P(k): input vs output (log10-log10)"),
+        )
+        .x_axis(Axis::default().title("log10 k [cycles/map]").bounds(x_bounds))
+        .y_axis(Axis::default().title("log10 P(k)").bounds(y_bounds));
+
+    chart.render(area, buf);
+}
+
+/// Pairs up `(k, P_in(k))` and `(k, P_out(k))` shells by their (shared,
+/// integer-valued) `k`, returning `(log10 k, P_out/P_in)` for every shell
+/// present and positive in both. Dropped shells (present in only one map,
+/// or with non-positive power) just leave a gap in the curve.
+fn boost_ratio_points(k_in: &[f32], p_in: &[f32], k_out: &[f32], p_out: &[f32]) -> Vec<(f64, f64)> {
+    k_in.iter()
+        .zip(p_in.iter())
+        .filter(|(_, &p)| p > 0.0)
+        .filter_map(|(&k, &p_in_val)| {
+            let out_idx = k_out.iter().position(|&ko| (ko - k).abs() < 1e-6)?;
+            let p_out_val = p_out[out_idx];
+            if p_out_val <= 0.0 {
+                return None;
+            }
+            Some((k.log10() as f64, (p_out_val / p_in_val) as f64))
+        })
+        .collect()
+}
+
+/// The "baryonic boost" panel: the ratio `P_out(k)/P_in(k)` of the output
+/// map's power spectrum to the input's, which is exactly the quantity the
+/// `Driver`'s N-body-to-hydro inference is meant to predict. A flat line at
+/// 1 means no baryonic effect was applied at that scale; deviations above
+/// or below 1 show scale-dependent enhancement or suppression.
+fn render_boost_chart(input_map: &Tensor, output_map: &Tensor, area: Rect, buf: &mut Buffer) {
+    let (k_in, p_in) = power_spectrum_2d(input_map);
+    let (k_out, p_out) = power_spectrum_2d(output_map);
+    let boost_points = boost_ratio_points(&k_in, &p_in, &k_out, &p_out);
+
+    let x_bounds = axis_bounds(&boost_points, |p| p.0);
+    let y_bounds = axis_bounds(&boost_points, |p| p.1);
+
+    let datasets = vec![Dataset::default()
+        .name("P_out / P_in")
+        .marker(symbols::Marker::Braille)
+        .graph_type(GraphType::Line)
+        .style(Style::default().fg(Color::Yellow))
+        .data(&boost_points)];
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Baryonic boost: P_out/P_in"),
+        )
+        .x_axis(Axis::default().title("log10 k [cycles/map]").bounds(x_bounds))
+        .y_axis(Axis::default().title("P_out/P_in").bounds(y_bounds));
+
+    chart.render(area, buf);
+}
+
+fn render_pdf_chart(input_map: &Tensor, output_map: &Tensor, area: Rect, buf: &mut Buffer) {
+    const NUM_BINS: usize = 40;
+    let (x_in, y_in) = log_pdf_histogram(input_map, NUM_BINS);
+    let (x_out, y_out) = log_pdf_histogram(output_map, NUM_BINS);
+
+    let input_points: Vec<(f64, f64)> = x_in
+        .iter()
+        .zip(y_in.iter())
+        .map(|(&x, &y)| (x as f64, y as f64))
+        .collect();
+    let output_points: Vec<(f64, f64)> = x_out
+        .iter()
+        .zip(y_out.iter())
+        .map(|(&x, &y)| (x as f64, y as f64))
+        .collect();
+
+    let all_points: Vec<(f64, f64)> = input_points.iter().chain(output_points.iter()).copied().collect();
+    let x_bounds = axis_bounds(&all_points, |p| p.0);
+    let y_bounds = axis_bounds(&all_points, |p| p.1);
+
+    let datasets = vec![
+        Dataset::default()
+            .name("input")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Blue))
+            .data(&input_points),
+        Dataset::default()
+            .name("output")
+            .marker(symbols::Marker::Braille)
+            .graph_type(GraphType::Line)
+            .style(Style::default().fg(Color::Green))
+            .data(&output_points),
+    ];
+
+    let chart = Chart::new(datasets)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Pixel PDF (log1p-space)"),
+        )
+        .x_axis(Axis::default().title("log1p(value)").bounds(x_bounds))
+        .y_axis(Axis::default().title("density").bounds(y_bounds));
+
+    chart.render(area, buf);
+}
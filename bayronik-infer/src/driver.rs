@@ -0,0 +1,165 @@
+//! Runs N-body generation + inference on a background worker thread, so
+//! `run_app_logic`'s render tick never blocks on it the way a synchronous
+//! `switch_to_nbody` call used to. `Driver::request_nbody` enqueues a job on
+//! an `mpsc` channel; `Driver::poll` drains whatever progress/result
+//! messages have arrived since the last call without blocking, for
+//! `run_app_logic` to fold into `App`'s `JobState` once per tick.
+
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use tch::{CModule, Device, Kind, Tensor};
+
+/// The fixed N-body run configuration `generate_nbody_map` used to hardcode
+/// directly; factored out so the worker thread can reuse it per job.
+const GRID_RESOLUTION: usize = 64;
+const BOX_SIZE: f32 = 100.0;
+const TIME_STEP: f32 = 0.005;
+const NUM_STEPS: usize = 80;
+const PROJECTION_RES: usize = 256;
+
+struct Job {
+    seed: u64,
+}
+
+/// Inference precision: `Fp32` runs the model and all tensors at full
+/// precision; `Half` casts both to `Kind::Half` before `forward_ts` and
+/// casts the result back to float afterward, roughly halving memory and
+/// speeding up the forward pass on GPUs that support it. Resolving which
+/// one is actually usable (half needs a CUDA device) happens once at
+/// startup in `main`'s config parsing, not here — by the time a `Precision`
+/// reaches `Driver`/`run_inference` it's already the one to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    Fp32,
+    Half,
+}
+
+/// Progress/result messages the worker thread sends back to the UI thread.
+pub enum DriverMessage {
+    /// `(completed_steps, total_steps)` of the leapfrog integration.
+    Progress(usize, usize),
+    /// The finished `(input_map, output_map)` pair, log1p-scaled and
+    /// forward-passed through the model exactly like `load_simulation`'s.
+    Done { input_map: Tensor, output_map: Tensor },
+    /// The job failed; carries `err.to_string()` since `anyhow::Error`
+    /// itself isn't `Send + 'static` across an arbitrary channel boundary.
+    Failed(String),
+}
+
+/// Owns the worker thread and the two channels connecting it to the UI
+/// thread. Dropping a `Driver` closes the job channel, which ends the
+/// worker's `for job in job_rx` loop and lets its thread exit.
+pub struct Driver {
+    job_tx: Sender<Job>,
+    msg_rx: Receiver<DriverMessage>,
+}
+
+impl Driver {
+    /// Spawns the worker thread, which loads its own copy of the model at
+    /// `model_path` so it never shares `tch::CModule` state with the UI
+    /// thread's copy used for CAMELS navigation. If `precision` is `Half`,
+    /// the worker's model copy is cast to `Kind::Half` once up front rather
+    /// than per job.
+    pub fn spawn(model_path: &str, device: Device, precision: Precision) -> anyhow::Result<Self> {
+        let mut model = CModule::load_on_device(model_path, device)?;
+        if precision == Precision::Half {
+            model.to(device, Kind::Half, false);
+        }
+        let (job_tx, job_rx) = mpsc::channel::<Job>();
+        let (msg_tx, msg_rx) = mpsc::channel::<DriverMessage>();
+
+        thread::spawn(move || {
+            for job in job_rx {
+                let msg_tx_progress = msg_tx.clone();
+                let nbody_map = bayronik_core::run_simulation_with_progress(
+                    GRID_RESOLUTION,
+                    BOX_SIZE,
+                    TIME_STEP,
+                    NUM_STEPS,
+                    PROJECTION_RES,
+                    bayronik_core::AssignmentScheme::Cic,
+                    job.seed,
+                    true,
+                    move |step| {
+                        let _ = msg_tx_progress.send(DriverMessage::Progress(step + 1, NUM_STEPS));
+                    },
+                );
+
+                let result = run_inference(&model, device, precision, nbody_map);
+                let msg = match result {
+                    Ok((input_map, output_map)) => DriverMessage::Done { input_map, output_map },
+                    Err(err) => DriverMessage::Failed(err.to_string()),
+                };
+                if msg_tx.send(msg).is_err() {
+                    // UI thread is gone; no point finishing remaining jobs.
+                    break;
+                }
+            }
+        });
+
+        Ok(Self { job_tx, msg_rx })
+    }
+
+    /// Enqueues an N-body generation job with the given IC seed. Returns
+    /// immediately; progress and the result arrive later through `poll`.
+    pub fn request_nbody(&self, seed: u64) {
+        // The only way this fails is if the worker thread has already
+        // exited (e.g. it panicked); there's nothing useful to do with that
+        // here beyond not panicking the UI thread too.
+        let _ = self.job_tx.send(Job { seed });
+    }
+
+    /// Drains every message currently queued, without blocking. Call once
+    /// per render tick.
+    pub fn poll(&self) -> Vec<DriverMessage> {
+        self.msg_rx.try_iter().collect()
+    }
+}
+
+/// Rescales a raw N-body density map to roughly match the CAMELS Mcdm
+/// log-space statistics, then runs it through `model`. Factored out of the
+/// old synchronous `switch_to_nbody` so the worker thread can call it
+/// directly on its own copy of the model.
+fn run_inference(
+    model: &CModule,
+    device: Device,
+    precision: Precision,
+    mut nbody_map: Vec<f32>,
+) -> anyhow::Result<(Tensor, Tensor)> {
+    let nbody_mean: f32 = nbody_map.iter().sum::<f32>() / nbody_map.len() as f32;
+    let nbody_std: f32 = (nbody_map
+        .iter()
+        .map(|&x| (x - nbody_mean).powi(2))
+        .sum::<f32>()
+        / nbody_map.len() as f32)
+        .sqrt();
+
+    // Scale and shift to match CAMELS Mcdm log-space statistics.
+    // Target: mean ~10^10, but after log1p we want log-space mean ~22-23, std ~2.5
+    let target_mean = 1e10;
+    let target_std_ratio = 0.7; // Reduce variance to match CAMELS better
+
+    for val in &mut nbody_map {
+        // Standardize then rescale variance
+        *val = (*val - nbody_mean) / nbody_std * (nbody_mean * target_std_ratio) + nbody_mean;
+        *val = (*val).max(0.0); // Ensure positive
+        *val *= target_mean / nbody_mean;
+    }
+
+    let input_map = Tensor::from_slice(&nbody_map)
+        .reshape([1, 1, PROJECTION_RES as i64, PROJECTION_RES as i64])
+        .to_kind(Kind::Float)
+        .log1p();
+
+    let model_input = if precision == Precision::Half {
+        input_map.to_kind(Kind::Half)
+    } else {
+        input_map.shallow_clone()
+    };
+    let output_map = model
+        .forward_ts(&[model_input.to(device)])?
+        .to(Device::Cpu)
+        .to_kind(Kind::Float);
+
+    Ok((input_map, output_map))
+}
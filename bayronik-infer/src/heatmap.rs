@@ -4,18 +4,97 @@ use ratatui::style::Color as RatatuiColor;
 use ratatui::widgets::{Block, Borders, Widget};
 use tch::Tensor;
 
+/// Sends `[0, inf)` to `[0, 1)`, with `x == typical` landing exactly on
+/// `0.5`. Unlike min/max normalization this never saturates: a handful of
+/// outlier pixels can no longer wash out the rest of the field, since
+/// arbitrarily large `x` only asymptotes toward (never reaches) 1.
+fn scale_unsigned(x: f64, typical: f64) -> f64 {
+    if typical <= 0.0 {
+        return if x > 0.0 { 0.999 } else { 0.0 };
+    }
+    1.0 - 1.0 / (x / typical + 1.0)
+}
+
+/// Applies `scale_unsigned` to `|x|` and reattaches `x`'s sign, sending
+/// `(-inf, inf)` to `(-1, 1)`. The natural extension of `scale_unsigned` to
+/// signed fields like a baryonic-effect Δ map, where both the sign and the
+/// magnitude of a deviation matter.
+fn scale_signed(x: f64, typical: f64) -> f64 {
+    x.signum() * scale_unsigned(x.abs(), typical)
+}
+
+/// The unsigned (single-hue, cold-to-hot) palette used for fields that are
+/// non-negative by construction (e.g. the log-space Mcdm/Mtot maps).
+/// `t` is expected in `[0, 1)`, as produced by `scale_unsigned`.
+fn sequential_color(t: f64) -> RatatuiColor {
+    if t < 0.2 {
+        RatatuiColor::Rgb(50, 50, 80)
+    } else if t < 0.4 {
+        RatatuiColor::Rgb(80, 100, 180)
+    } else if t < 0.6 {
+        RatatuiColor::Rgb(150, 180, 220)
+    } else if t < 0.8 {
+        RatatuiColor::Rgb(255, 200, 100)
+    } else {
+        RatatuiColor::Rgb(255, 100, 100)
+    }
+}
+
+/// The diverging (blue-white-red) palette used for signed fields, e.g. the
+/// baryonic-effect Δ map. `t` is expected in `(-1, 1)`, as produced by
+/// `scale_signed`: negative values shade toward blue, positive toward red,
+/// and values near zero stay a neutral near-white so subtle suppression or
+/// enhancement doesn't get lost against a single dominant hue.
+fn diverging_color(t: f64) -> RatatuiColor {
+    let t = t.clamp(-1.0, 1.0);
+    let lerp = |a: u8, b: u8, f: f64| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+    const NEGATIVE: (u8, u8, u8) = (30, 60, 200);
+    const NEUTRAL: (u8, u8, u8) = (235, 235, 235);
+    const POSITIVE: (u8, u8, u8) = (210, 40, 40);
+
+    let (from, to, f) = if t < 0.0 {
+        (NEGATIVE, NEUTRAL, 1.0 + t)
+    } else {
+        (NEUTRAL, POSITIVE, t)
+    };
+    RatatuiColor::Rgb(
+        lerp(from.0, to.0, f),
+        lerp(from.1, to.1, f),
+        lerp(from.2, to.2, f),
+    )
+}
+
 pub struct HeatmapWidget<'a> {
     tensor: &'a Tensor,
     title: &'a str,
     border_color: RatatuiColor,
+    /// The "typical" scale passed to `scale_unsigned`/`scale_signed`: the
+    /// field value that should map to the midpoint of its palette.
+    typical: f64,
 }
 
 impl<'a> HeatmapWidget<'a> {
-    pub fn new(tensor: &'a Tensor, title: &'a str, border_color: RatatuiColor) -> Self {
+    /// `typical` sets the scale at which `scale_unsigned`/`scale_signed`
+    /// saturate to half-intensity; pass `None` to default to the tensor's
+    /// standard deviation, a robust stand-in for its typical magnitude.
+    /// Whether the panel renders with the unsigned or diverging palette is
+    /// decided automatically from the data itself: a field with any
+    /// negative values (e.g. a baryonic-effect Δ map) renders diverging,
+    /// otherwise unsigned.
+    pub fn new(tensor: &'a Tensor, title: &'a str, border_color: RatatuiColor, typical: Option<f64>) -> Self {
+        let typical = typical.unwrap_or_else(|| {
+            let std_val = tensor.std(true).double_value(&[]);
+            if std_val > 1e-9 {
+                std_val
+            } else {
+                1.0
+            }
+        });
         Self {
             tensor,
             title,
             border_color,
+            typical,
         }
     }
 
@@ -99,9 +178,10 @@ impl Widget for HeatmapWidget<'_> {
             None => return,
         };
 
-        let data_min = data.iter().cloned().fold(f64::INFINITY, f64::min);
-        let data_max = data.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
-        let range = if data_max > data_min { data_max - data_min } else { 1.0 };
+        // A field with any negative values (e.g. output - input) is treated
+        // as signed and rendered with the diverging palette; a non-negative
+        // field (e.g. a log-space density map) keeps the unsigned palette.
+        let is_signed = data.iter().any(|&v| v < 0.0);
 
         const BRAILLE: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
 
@@ -116,10 +196,19 @@ impl Widget for HeatmapWidget<'_> {
 
                         if pixel_x < plot_width && pixel_y < plot_height {
                             let idx = pixel_y * plot_width + pixel_x;
-                            let val = data[idx];
-                            let normalized = ((val - data_min) / range).clamp(0.0, 1.0);
-
-                            if normalized > 0.25 {
+                            // Unsigned panels (e.g. log-space density maps)
+                            // sit on a large DC offset (`min_val`); scaling
+                            // the raw value would put nearly every pixel in
+                            // the saturated tail. Scaling the offset-from-baseline
+                            // value instead restores the contrast a plain
+                            // (val-min)/range normalization gave.
+                            let intensity = if is_signed {
+                                scale_unsigned(data[idx].abs(), self.typical)
+                            } else {
+                                scale_unsigned((data[idx] - min_val).max(0.0), self.typical)
+                            };
+
+                            if intensity > 0.25 {
                                 braille_char |= BRAILLE[dot_y][dot_x] as u32;
                             }
                         }
@@ -127,7 +216,7 @@ impl Widget for HeatmapWidget<'_> {
                 }
 
                 let ch = char::from_u32(braille_char).unwrap_or('?');
-                    let color = {
+                let color = {
                     let mut sum = 0.0;
                     let mut count = 0;
                     for dot_y in 0..4 {
@@ -141,18 +230,11 @@ impl Widget for HeatmapWidget<'_> {
                         }
                     }
                     let avg = if count > 0 { sum / count as f64 } else { 0.0 };
-                    let normalized = ((avg - data_min) / range).clamp(0.0, 1.0);
-
-                    if normalized < 0.2 {
-                        RatatuiColor::Rgb(50, 50, 80)
-                    } else if normalized < 0.4 {
-                        RatatuiColor::Rgb(80, 100, 180)
-                    } else if normalized < 0.6 {
-                        RatatuiColor::Rgb(150, 180, 220)
-                    } else if normalized < 0.8 {
-                        RatatuiColor::Rgb(255, 200, 100)
+
+                    if is_signed {
+                        diverging_color(scale_signed(avg, self.typical))
                     } else {
-                        RatatuiColor::Rgb(255, 100, 100)
+                        sequential_color(scale_unsigned((avg - min_val).max(0.0), self.typical))
                     }
                 };
 
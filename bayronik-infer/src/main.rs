@@ -1,4 +1,7 @@
+mod diagnostics;
+mod driver;
 mod heatmap;
+mod render;
 
 use anyhow::{Context, Result};
 use crossterm::{
@@ -6,20 +9,177 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use diagnostics::DiagnosticsWidget;
+use driver::{Driver, DriverMessage, Precision};
 use heatmap::HeatmapWidget;
 use npyz::NpyFile;
 use ratatui::{
     prelude::*,
     widgets::{Block, Borders, Paragraph},
 };
-use std::{fs::File, io::{self, Stdout}, time::{Duration, Instant}};
+use std::{
+    fs::File,
+    io::{self, Stdout},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 use tch::{kind::Kind, Device, Tensor};
 
+/// Default number of simulations batched into a single `forward_ts` call
+/// during `precompute_output_cache`, overridable via `BAYRONIK_CHUNK_SIZE`.
+/// Kept far smaller on CPU, where there's no VRAM ceiling forcing the issue
+/// but a large batch still means a long pause before the first frame draws.
+const DEFAULT_GPU_CHUNK_SIZE: usize = 64;
+const DEFAULT_CPU_CHUNK_SIZE: usize = 8;
+
+/// Picks the precompute batch size: `BAYRONIK_CHUNK_SIZE` if set and valid,
+/// otherwise a default sized to whether `device` is a GPU.
+fn chunk_size_for(device: Device) -> usize {
+    if let Ok(configured) = std::env::var("BAYRONIK_CHUNK_SIZE") {
+        if let Ok(size) = configured.parse::<usize>() {
+            if size > 0 {
+                return size;
+            }
+        }
+    }
+    if device.is_cuda() {
+        DEFAULT_GPU_CHUNK_SIZE
+    } else {
+        DEFAULT_CPU_CHUNK_SIZE
+    }
+}
+
+/// Runs `model.forward_ts` once per `chunk_size`-sized batch over the full
+/// `all_input_maps` dataset (stacking along the batch dimension) instead of
+/// once per simulation, so `App::load_simulation` becomes an O(1) cache
+/// lookup with no further GPU round-trip. Batching bounds peak VRAM to
+/// `chunk_size` frames regardless of `total_sims`, so this scales to large
+/// CV/LH datasets without needing the whole dataset resident on the GPU at
+/// once.
+fn precompute_output_cache(
+    model: &tch::CModule,
+    device: Device,
+    precision: Precision,
+    all_input_maps: &[f32],
+    total_sims: usize,
+    side: usize,
+    chunk_size: usize,
+) -> Result<Vec<Tensor>> {
+    let mut cache = Vec::with_capacity(total_sims);
+    for chunk_start in (0..total_sims).step_by(chunk_size) {
+        let chunk_end = (chunk_start + chunk_size).min(total_sims);
+        let batch_len = chunk_end - chunk_start;
+
+        let start = chunk_start * side * side;
+        let end = chunk_end * side * side;
+        let input_batch = Tensor::from_slice(&all_input_maps[start..end])
+            .reshape([batch_len as i64, 1, side as i64, side as i64])
+            .to_kind(Kind::Float)
+            .log1p();
+        let model_input = if precision == Precision::Half {
+            input_batch.to_kind(Kind::Half)
+        } else {
+            input_batch.shallow_clone()
+        };
+
+        let output_batch = model
+            .forward_ts(&[model_input.to(device)])?
+            .to(Device::Cpu)
+            .to_kind(Kind::Float);
+        for i in 0..batch_len {
+            cache.push(output_batch.get(i as i64).unsqueeze(0));
+        }
+    }
+    Ok(cache)
+}
+
+/// Startup device/precision selection, parsed once in `main` and threaded
+/// into every place that loads or runs the model (`App::new`'s own copy and
+/// `Driver::spawn`'s background copy), so both stay consistent with what
+/// the user asked for.
+struct AppConfig {
+    device: Device,
+    precision: Precision,
+}
+
+impl AppConfig {
+    /// Reads `--device`/`--precision` flags out of `args` (falling back to
+    /// the `BAYRONIK_DEVICE`/`BAYRONIK_PRECISION` env vars, then to
+    /// defaults), the same manual flag-parsing style `run_batch_export`
+    /// uses. `--device` accepts `auto` (default; `Device::cuda_if_available`),
+    /// `cpu`, `cuda`, or `cuda:N`. `--precision` accepts `fp32` (default) or
+    /// `fp16`/`half`; half is silently downgraded to `fp32` on a CPU device,
+    /// since `tch`/libtorch half-precision ops need a CUDA device.
+    fn parse(args: &[String]) -> Result<Self> {
+        let mut device_spec = std::env::var("BAYRONIK_DEVICE").ok();
+        let mut precision_spec = std::env::var("BAYRONIK_PRECISION").ok();
+
+        let mut i = 0;
+        while i < args.len() {
+            match args[i].as_str() {
+                "--device" => {
+                    i += 1;
+                    device_spec = Some(
+                        args.get(i)
+                            .cloned()
+                            .with_context(|| "Missing value for --device")?,
+                    );
+                }
+                "--precision" => {
+                    i += 1;
+                    precision_spec = Some(
+                        args.get(i)
+                            .cloned()
+                            .with_context(|| "Missing value for --precision")?,
+                    );
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+
+        let device = match device_spec.as_deref() {
+            None | Some("auto") => Device::cuda_if_available(),
+            Some("cpu") => Device::Cpu,
+            Some("cuda") => Device::Cuda(0),
+            Some(spec) => {
+                let idx = spec
+                    .strip_prefix("cuda:")
+                    .with_context(|| format!("Unrecognized --device value '{}'", spec))?
+                    .parse::<usize>()
+                    .with_context(|| format!("Invalid CUDA device index in '{}'", spec))?;
+                Device::Cuda(idx)
+            }
+        };
+
+        let precision = match precision_spec.as_deref() {
+            None | Some("fp32") => Precision::Fp32,
+            Some("fp16") | Some("half") => {
+                if device.is_cuda() {
+                    Precision::Half
+                } else {
+                    Precision::Fp32
+                }
+            }
+            Some(other) => anyhow::bail!("Unrecognized --precision value '{}'", other),
+        };
+
+        Ok(Self { device, precision })
+    }
+}
+
 enum DataSource {
     CamelsCV,
     NBodyGenerated,
 }
 
+/// Tracks the background `Driver` job, if any, so `ui` can show live
+/// progress instead of the UI thread just blocking until it's done.
+enum JobState {
+    Idle,
+    Running { completed_steps: usize, total_steps: usize },
+}
+
 struct App {
     input_map: Tensor,
     output_map: Tensor,
@@ -30,10 +190,17 @@ struct App {
     device: Device,
     data_source: DataSource,
     status_message: String,
+    driver: Driver,
+    job_state: JobState,
+    /// Precomputed `model.forward_ts` output for every CAMELS simulation in
+    /// `all_input_maps`, indexed the same way. Built once in `App::new` by
+    /// `precompute_output_cache`; `load_simulation` looks this up instead of
+    /// re-running inference on every navigation keypress.
+    output_cache: Vec<Tensor>,
 }
 
 impl App {
-    fn new() -> Result<Self> {
+    fn new(config: &AppConfig) -> Result<Self> {
         println!("Loading dataset and TorchScript model...");
         
         let npy_path = "../bayronik-model/data/Maps_Mcdm_IllustrisTNG_CV_z=0.00.npy";
@@ -50,21 +217,42 @@ impl App {
         
         let model_path = "../bayronik-model/weights/traced_unet_LH.pt";
         println!("   Loading model: {}", model_path);
-        let device = Device::cuda_if_available();
-        let model = tch::CModule::load_on_device(model_path, device)
+        let device = config.device;
+        let mut model = tch::CModule::load_on_device(model_path, device)
             .with_context(|| format!("Failed to load TorchScript model from '{}'", model_path))?;
-        println!("   ✓ Model loaded on {:?}", device);
-        
+        if config.precision == Precision::Half {
+            model.to(device, Kind::Half, false);
+        }
+        println!("   ✓ Model loaded on {:?} ({:?})", device, config.precision);
+
+        let chunk_size = chunk_size_for(device);
+        println!(
+            "   Precomputing {} outputs (batch size {})...",
+            total_sims, chunk_size
+        );
+        let output_cache = precompute_output_cache(
+            &model,
+            device,
+            config.precision,
+            &all_data,
+            total_sims,
+            256,
+            chunk_size,
+        )?;
+        println!("   ✓ Precompute done");
+
         let first_sim_data = &all_data[..256 * 256];
-        let input_map_raw = Tensor::from_slice(first_sim_data)
+        let input_map = Tensor::from_slice(first_sim_data)
             .reshape(&[1, 1, 256, 256])
-            .to_kind(Kind::Float);
-        
-        let input_map = input_map_raw.log1p();
-        let output_map = model.forward_ts(&[input_map.to(device)])?.to(Device::Cpu);
-        
+            .to_kind(Kind::Float)
+            .log1p();
+        let output_map = output_cache[0].shallow_clone();
+
         println!("✅ Ready! Use ← → arrows to navigate simulations");
-        
+
+        let driver = Driver::spawn(model_path, device, config.precision)
+            .with_context(|| "Failed to spawn background N-body/inference driver")?;
+
         Ok(Self {
             input_map,
             output_map,
@@ -75,57 +263,45 @@ impl App {
             device,
             data_source: DataSource::CamelsCV,
             status_message: String::new(),
+            driver,
+            job_state: JobState::Idle,
+            output_cache,
         })
     }
-    
-    fn generate_nbody_map(&self) -> Vec<f32> {
-        bayronik_core::run_simulation(
-            131_072,  // 2^17 particles - reduces shot noise
-            64,       // 64^3 grid - better force resolution
-            100.0,    // 100 Mpc box
-            0.005,    // Smaller timestep for stability
-            80,       // 2× more steps to break grid symmetry
-            256,      // 256x256 output resolution
-        )
-    }
-    
-    fn switch_to_nbody(&mut self) -> Result<()> {
+
+    /// Enqueues an N-body generation job on the background `Driver` and
+    /// returns immediately; `apply_driver_messages` folds the result in once
+    /// the worker thread finishes, instead of this call blocking the render
+    /// loop the way the old synchronous `switch_to_nbody` used to.
+    fn request_nbody(&mut self, seed: u64) {
+        self.driver.request_nbody(seed);
+        self.job_state = JobState::Running { completed_steps: 0, total_steps: 80 };
         self.status_message = "Generating N-body simulation...".to_string();
-        
-        let mut nbody_map = self.generate_nbody_map();
-        
-        // Match CAMELS statistics in log-space
-        let nbody_mean: f32 = nbody_map.iter().sum::<f32>() / nbody_map.len() as f32;
-        let nbody_std: f32 = (nbody_map.iter()
-            .map(|&x| (x - nbody_mean).powi(2))
-            .sum::<f32>() / nbody_map.len() as f32).sqrt();
-        
-        // Scale and shift to match CAMELS Mcdm log-space statistics
-        // Target: mean ~10^10, but after log1p we want log-space mean ~22-23, std ~2.5
-        let target_mean = 1e10;
-        let target_std_ratio = 0.7;  // Reduce variance to match CAMELS better
-        
-        for val in &mut nbody_map {
-            // Standardize then rescale variance
-            *val = (*val - nbody_mean) / nbody_std * (nbody_mean * target_std_ratio) + nbody_mean;
-            *val = (*val).max(0.0);  // Ensure positive
-            *val *= target_mean / nbody_mean;
+    }
+
+    /// Drains whatever `DriverMessage`s have arrived since the last call and
+    /// applies them to `self`. Call once per render tick.
+    fn apply_driver_messages(&mut self) {
+        for msg in self.driver.poll() {
+            match msg {
+                DriverMessage::Progress(completed_steps, total_steps) => {
+                    self.job_state = JobState::Running { completed_steps, total_steps };
+                }
+                DriverMessage::Done { input_map, output_map } => {
+                    self.input_map = input_map;
+                    self.output_map = output_map;
+                    self.data_source = DataSource::NBodyGenerated;
+                    self.status_message = "N-body simulation complete".to_string();
+                    self.job_state = JobState::Idle;
+                }
+                DriverMessage::Failed(err) => {
+                    self.status_message = format!("N-body generation failed: {}", err);
+                    self.job_state = JobState::Idle;
+                }
+            }
         }
-        
-        let input_map_raw = Tensor::from_slice(&nbody_map)
-            .reshape(&[1, 1, 256, 256])
-            .to_kind(Kind::Float);
-        
-        self.input_map = input_map_raw.log1p();
-        self.output_map = self.model
-            .forward_ts(&[self.input_map.to(self.device)])?
-            .to(Device::Cpu);
-        
-        self.data_source = DataSource::NBodyGenerated;
-        self.status_message = "N-body simulation complete".to_string();
-        Ok(())
     }
-    
+
     fn switch_to_camels(&mut self) -> Result<()> {
         self.status_message = "Switching to CAMELS data...".to_string();
         self.load_simulation(0)?;
@@ -138,23 +314,26 @@ impl App {
         let start = idx * 256 * 256;
         let end = start + 256 * 256;
         let sim_data = &self.all_input_maps[start..end];
-        
-        let input_map_raw = Tensor::from_slice(sim_data)
+
+        self.input_map = Tensor::from_slice(sim_data)
             .reshape(&[1, 1, 256, 256])
-            .to_kind(Kind::Float);
-        
-        self.input_map = input_map_raw.log1p();
-        self.output_map = self.model
-            .forward_ts(&[self.input_map.to(self.device)])?
-            .to(Device::Cpu);
-        
+            .to_kind(Kind::Float)
+            .log1p();
+        self.output_map = self.output_cache[idx].shallow_clone();
+
         self.current_sim_idx = idx;
         Ok(())
     }
 }
 
 fn main() -> Result<()> {
-    let app = App::new()?;
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("batch-export") {
+        return run_batch_export(&args[2..]);
+    }
+
+    let config = AppConfig::parse(&args[1..])?;
+    let app = App::new(&config)?;
     let mut terminal = setup_terminal()?;
     let result = run_app_logic(&mut terminal, app);
     restore_terminal(&mut terminal)?;
@@ -164,11 +343,87 @@ fn main() -> Result<()> {
     Ok(())
 }
 
+/// Headless `batch-export` subcommand: loads the dataset and model exactly
+/// like the interactive TUI does, then runs `render::export_batch` over
+/// `[--start, --end)` (defaulting to the full dataset) without ever calling
+/// `enable_raw_mode`, so this can run in a plain shell or CI.
+///
+/// Flags: `--output-dir DIR` (default `batch_export`), `--start N`
+/// (default 0), `--end N` (default `total_sims`), `--video PATH` (optional
+/// y4m fly-through of the composited frames), plus the `--device`/
+/// `--precision` flags `AppConfig::parse` reads (also accepted here so the
+/// same device/precision selection applies to batch export).
+fn run_batch_export(args: &[String]) -> Result<()> {
+    let mut output_dir = PathBuf::from("batch_export");
+    let mut start = 0usize;
+    let mut end: Option<usize> = None;
+    let mut video_path: Option<PathBuf> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--output-dir" => {
+                i += 1;
+                output_dir = PathBuf::from(
+                    args.get(i)
+                        .with_context(|| "Missing value for --output-dir")?,
+                );
+            }
+            "--start" => {
+                i += 1;
+                let value = args.get(i).with_context(|| "Missing value for --start")?;
+                start = value
+                    .parse()
+                    .with_context(|| format!("Invalid --start value '{}'", value))?;
+            }
+            "--end" => {
+                i += 1;
+                let value = args.get(i).with_context(|| "Missing value for --end")?;
+                end = Some(
+                    value
+                        .parse()
+                        .with_context(|| format!("Invalid --end value '{}'", value))?,
+                );
+            }
+            "--video" => {
+                i += 1;
+                video_path = Some(PathBuf::from(
+                    args.get(i).with_context(|| "Missing value for --video")?,
+                ));
+            }
+            "--device" | "--precision" => {
+                // Consumed by `AppConfig::parse` below; just skip the value.
+                i += 1;
+                if i >= args.len() {
+                    anyhow::bail!("Missing value for '{}'", args[i - 1]);
+                }
+            }
+            other => anyhow::bail!("Unrecognized batch-export argument '{}'", other),
+        }
+        i += 1;
+    }
+
+    let config = AppConfig::parse(args)?;
+    let app = App::new(&config)?;
+    let end = end.unwrap_or(app.total_sims);
+    render::export_batch(
+        &app.all_input_maps,
+        app.total_sims,
+        256,
+        &app.model,
+        app.device,
+        start..end,
+        &output_dir,
+        video_path.as_deref(),
+    )
+}
+
 fn run_app_logic(terminal: &mut Terminal<CrosstermBackend<Stdout>>, mut app: App) -> Result<()> {
     let mut last_tick = Instant::now();
     let tick_rate = Duration::from_millis(250);
 
     loop {
+        app.apply_driver_messages();
         terminal.draw(|f| ui(f, &app))?;
         let timeout = tick_rate.saturating_sub(last_tick.elapsed());
         if crossterm::event::poll(timeout)? {
@@ -199,11 +454,9 @@ fn run_app_logic(terminal: &mut Terminal<CrosstermBackend<Stdout>>, mut app: App
                         }
                     }
                     KeyCode::Char('g') => {
-                        terminal.draw(|f| {
-                            app.status_message = "Generating N-body simulation...".to_string();
-                            ui(f, &app);
-                        })?;
-                        app.switch_to_nbody()?;
+                        if matches!(app.job_state, JobState::Idle) {
+                            app.request_nbody(42);
+                        }
                     }
                     KeyCode::Char('c') => {
                         app.switch_to_camels()?;
@@ -225,6 +478,7 @@ fn ui(frame: &mut Frame, app: &App) {
             Constraint::Length(3),
             Constraint::Length(4),
             Constraint::Min(0),
+            Constraint::Length(12),
         ])
         .split(frame.area());
     
@@ -244,13 +498,18 @@ fn ui(frame: &mut Frame, app: &App) {
         DataSource::NBodyGenerated => "N-Body Generated (Press 'c' to return to CAMELS)".to_string(),
     };
     
-    let controls_text = if app.status_message.is_empty() {
-        format!(
+    let controls_text = match &app.job_state {
+        JobState::Running { completed_steps, total_steps } => {
+            format!(
+                "{} | ⚡ Generating N-body ({}/{})",
+                source_label, completed_steps, total_steps
+            )
+        }
+        JobState::Idle if app.status_message.is_empty() => format!(
             "{} | [←/→] Nav | [r] Rand | [g] Gen N-body | [c] CAMELS | [q] Quit",
             source_label
-        )
-    } else {
-        format!("{} | ⚡ {}", source_label, app.status_message)
+        ),
+        JobState::Idle => format!("{} | ⚡ {}", source_label, app.status_message),
     };
     
     let controls = Paragraph::new(controls_text)
@@ -267,15 +526,18 @@ fn ui(frame: &mut Frame, app: &App) {
         ])
         .split(main_layout[2]);
     
-    let input_widget = HeatmapWidget::new(&app.input_map, "Input: Dark Matter (Mcdm)", Color::Blue);
+    let input_widget = HeatmapWidget::new(&app.input_map, "Input: Dark Matter (Mcdm)", Color::Blue, None);
     frame.render_widget(input_widget, maps_layout[0]);
-    
-    let output_widget = HeatmapWidget::new(&app.output_map, "Output: Total Matter (Mtot)", Color::Green);
+
+    let output_widget = HeatmapWidget::new(&app.output_map, "Output: Total Matter (Mtot)", Color::Green, None);
     frame.render_widget(output_widget, maps_layout[1]);
-    
+
     let diff_map = &app.output_map - &app.input_map;
-    let diff_widget = HeatmapWidget::new(&diff_map, "Baryonic Effect (Δ)", Color::Red);
+    let diff_widget = HeatmapWidget::new(&diff_map, "Baryonic Effect (Δ)", Color::Red, None);
     frame.render_widget(diff_widget, maps_layout[2]);
+
+    let diagnostics_widget = DiagnosticsWidget::new(&app.input_map, &app.output_map);
+    frame.render_widget(diagnostics_widget, main_layout[3]);
 }
 
 fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>, io::Error> {
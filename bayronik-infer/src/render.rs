@@ -0,0 +1,226 @@
+//! Headless rasterization of the input/output/diff maps to true-color PNGs
+//! and a composited fly-through video, the offline counterpart to
+//! `heatmap`'s interactive terminal rendering. Used by `main`'s batch-export
+//! mode, which runs without `enable_raw_mode` so it can execute in CI or a
+//! plain shell.
+
+use anyhow::{Context, Result};
+use image::{Rgb, RgbImage};
+use std::fs::File;
+use std::path::Path;
+use tch::{Device, Tensor};
+
+/// Pixel gap between panels when compositing input/output/diff side-by-side.
+const PANEL_GAP: u32 = 4;
+
+/// Maps a value normalized to `[0, 1]` to an RGB color using the same five
+/// cold-to-hot colors as `HeatmapWidget`'s unsigned (sequential) palette.
+fn colormap(normalized: f64) -> Rgb<u8> {
+    let n = normalized.clamp(0.0, 1.0);
+    if n < 0.2 {
+        Rgb([50, 50, 80])
+    } else if n < 0.4 {
+        Rgb([80, 100, 180])
+    } else if n < 0.6 {
+        Rgb([150, 180, 220])
+    } else if n < 0.8 {
+        Rgb([255, 200, 100])
+    } else {
+        Rgb([255, 100, 100])
+    }
+}
+
+/// Rasterizes a 2D (after squeezing) tensor to an `RgbImage`, normalizing
+/// linearly by its own min/max. Unlike `HeatmapWidget::render`, this applies
+/// no `typical`-scale mapping and no diverging palette for signed fields, so
+/// the diff panel uses the same sequential ramp as the input/output panels.
+pub fn tensor_to_image(tensor: &Tensor) -> Result<RgbImage> {
+    let squeezed = tensor.squeeze().contiguous();
+    let dims = squeezed.size();
+    if dims.len() != 2 {
+        anyhow::bail!("expected a 2D tensor after squeezing, got shape {:?}", dims);
+    }
+    let (height, width) = (dims[0] as usize, dims[1] as usize);
+
+    let mut data = vec![0.0f32; height * width];
+    squeezed.copy_data(&mut data, height * width);
+
+    let data_min = data.iter().cloned().fold(f32::INFINITY, f32::min);
+    let data_max = data.iter().cloned().fold(f32::NEG_INFINITY, f32::max);
+    let range = if data_max > data_min { data_max - data_min } else { 1.0 };
+
+    let mut image = RgbImage::new(width as u32, height as u32);
+    for y in 0..height {
+        for x in 0..width {
+            let normalized = ((data[y * width + x] - data_min) / range) as f64;
+            image.put_pixel(x as u32, y as u32, colormap(normalized));
+        }
+    }
+    Ok(image)
+}
+
+/// Rasterizes `tensor` and writes it to `path` as a PNG.
+pub fn save_tensor_png(tensor: &Tensor, path: impl AsRef<Path>) -> Result<()> {
+    tensor_to_image(tensor)?
+        .save(path.as_ref())
+        .with_context(|| format!("Failed to write PNG to '{}'", path.as_ref().display()))
+}
+
+/// Composites the input, output, and diff tensors side-by-side (with a
+/// `PANEL_GAP`-pixel black gutter between them) into one frame, for a single
+/// frame of the batch fly-through video.
+pub fn compose_frame(input_map: &Tensor, output_map: &Tensor, diff_map: &Tensor) -> Result<RgbImage> {
+    let panels = [
+        tensor_to_image(input_map)?,
+        tensor_to_image(output_map)?,
+        tensor_to_image(diff_map)?,
+    ];
+
+    let panel_width = panels[0].width();
+    let panel_height = panels[0].height();
+    let frame_width = panel_width * panels.len() as u32 + PANEL_GAP * (panels.len() as u32 - 1);
+
+    let mut frame = RgbImage::from_pixel(frame_width, panel_height, Rgb([0, 0, 0]));
+    for (i, panel) in panels.iter().enumerate() {
+        let x_offset = i as u32 * (panel_width + PANEL_GAP);
+        for y in 0..panel_height {
+            for x in 0..panel_width {
+                frame.put_pixel(x_offset + x, y, *panel.get_pixel(x, y));
+            }
+        }
+    }
+    Ok(frame)
+}
+
+/// Streams composited frames into a y4m video file, one frame per call to
+/// `write_frame`.
+pub struct Y4mEncoder {
+    inner: y4m::Encoder<File>,
+}
+
+impl Y4mEncoder {
+    /// Creates `path` and writes the y4m stream header for a
+    /// `width`x`height`, 25fps video.
+    pub fn create(path: impl AsRef<Path>, width: u32, height: u32) -> Result<Self> {
+        let file = File::create(path.as_ref())
+            .with_context(|| format!("Failed to create video file '{}'", path.as_ref().display()))?;
+        let inner = y4m::encode(width as usize, height as usize, y4m::Ratio::new(25, 1))
+            .write_header(file)
+            .with_context(|| "Failed to write y4m stream header")?;
+        Ok(Self { inner })
+    }
+
+    /// Converts `frame` to planar 4:2:0 YCbCr and appends it to the stream.
+    pub fn write_frame(&mut self, frame: &RgbImage) -> Result<()> {
+        let (y_plane, u_plane, v_plane) = rgb_to_yuv420(frame);
+        let y4m_frame = y4m::Frame::new([&y_plane, &u_plane, &v_plane], None);
+        self.inner
+            .write_frame(&y4m_frame)
+            .with_context(|| "Failed to write y4m frame")
+    }
+}
+
+/// Converts an `RgbImage` to planar 4:2:0 YCbCr (studio-range BT.601), the
+/// pixel format `y4m` streams expect. Chroma is averaged over each 2x2
+/// luma block; an odd trailing row/column repeats its last pixel rather
+/// than reading out of bounds.
+fn rgb_to_yuv420(image: &RgbImage) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
+    let (width, height) = image.dimensions();
+    let (w, h) = (width as usize, height as usize);
+
+    let mut y_plane = vec![0u8; w * h];
+    for (i, pixel) in image.pixels().enumerate() {
+        let [r, g, b] = pixel.0;
+        y_plane[i] = rgb_to_y(r, g, b);
+    }
+
+    let (cw, ch) = ((w + 1) / 2, (h + 1) / 2);
+    let mut u_plane = vec![0u8; cw * ch];
+    let mut v_plane = vec![0u8; cw * ch];
+    for cy in 0..ch {
+        for cx in 0..cw {
+            let mut u_sum = 0i32;
+            let mut v_sum = 0i32;
+            let mut count = 0i32;
+            for dy in 0..2 {
+                for dx in 0..2 {
+                    let x = (cx * 2 + dx).min(w - 1);
+                    let y = (cy * 2 + dy).min(h - 1);
+                    let [r, g, b] = image.get_pixel(x as u32, y as u32).0;
+                    u_sum += rgb_to_u(r, g, b) as i32;
+                    v_sum += rgb_to_v(r, g, b) as i32;
+                    count += 1;
+                }
+            }
+            u_plane[cy * cw + cx] = (u_sum / count) as u8;
+            v_plane[cy * cw + cx] = (v_sum / count) as u8;
+        }
+    }
+
+    (y_plane, u_plane, v_plane)
+}
+
+fn rgb_to_y(r: u8, g: u8, b: u8) -> u8 {
+    (16.0 + (65.738 * r as f64 + 129.057 * g as f64 + 25.064 * b as f64) / 256.0) as u8
+}
+
+fn rgb_to_u(r: u8, g: u8, b: u8) -> u8 {
+    (128.0 + (-37.945 * r as f64 - 74.494 * g as f64 + 112.439 * b as f64) / 256.0) as u8
+}
+
+fn rgb_to_v(r: u8, g: u8, b: u8) -> u8 {
+    (128.0 + (112.439 * r as f64 - 94.154 * g as f64 - 18.285 * b as f64) / 256.0) as u8
+}
+
+/// Runs inference over `all_input_maps[range]` (each a `side*side` slice,
+/// clamped to `total_sims`), writing per-simulation input/output/diff PNGs
+/// into `output_dir` and, if `video_path` is given, streaming one
+/// composited frame per simulation into a y4m video there.
+pub fn export_batch(
+    all_input_maps: &[f32],
+    total_sims: usize,
+    side: usize,
+    model: &tch::CModule,
+    device: Device,
+    range: std::ops::Range<usize>,
+    output_dir: &Path,
+    video_path: Option<&Path>,
+) -> Result<()> {
+    std::fs::create_dir_all(output_dir)
+        .with_context(|| format!("Failed to create output directory '{}'", output_dir.display()))?;
+
+    let mut encoder = match video_path {
+        Some(path) => {
+            let frame_width = side as u32 * 3 + PANEL_GAP * 2;
+            Some(Y4mEncoder::create(path, frame_width, side as u32)?)
+        }
+        None => None,
+    };
+
+    for idx in range {
+        if idx >= total_sims {
+            break;
+        }
+        let start = idx * side * side;
+        let end = start + side * side;
+        let sim_data = &all_input_maps[start..end];
+
+        let input_map = Tensor::from_slice(sim_data)
+            .reshape([1, 1, side as i64, side as i64])
+            .to_kind(tch::Kind::Float)
+            .log1p();
+        let output_map = model.forward_ts(&[input_map.to(device)])?.to(Device::Cpu);
+        let diff_map = &output_map - &input_map;
+
+        save_tensor_png(&input_map, output_dir.join(format!("sim{:04}_input.png", idx)))?;
+        save_tensor_png(&output_map, output_dir.join(format!("sim{:04}_output.png", idx)))?;
+        save_tensor_png(&diff_map, output_dir.join(format!("sim{:04}_diff.png", idx)))?;
+
+        if let Some(encoder) = encoder.as_mut() {
+            let frame = compose_frame(&input_map, &output_map, &diff_map)?;
+            encoder.write_frame(&frame)?;
+        }
+    }
+
+    Ok(())
+}